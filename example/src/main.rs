@@ -1,27 +1,84 @@
 mod real;
 
 use crate::real::{AllowReason, BlockingState, DetectedRequest};
+#[cfg(test)]
+use serde_zod_runtime::ZodSchema;
+use serde_zod_runtime::{bundle, Dependency};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 ///
 /// This example shows how you would combine multiple items together
 /// into a single .ts file
 ///
 fn main() {
-    let lines = vec![
-        DetectedRequest::print_imports(),
-        AllowReason::codegen(),
-        BlockingState::codegen(),
-        DetectedRequest::codegen(),
-        Control::codegen(),
-        Test::codegen(),
-        TimerResult::codegen(),
-        Status::codegen(),
-        MixedEnum::codegen(),
-        UnitOnlyEnum::codegen(),
-        State::codegen(),
-    ];
-    fs::write("./app/types.ts", lines.join("\n")).expect("can write");
+    // each schema is declared after the ones it refers to, whatever the order here
+    let source = bundle(&[
+        Dependency::of::<AllowReason>(),
+        Dependency::of::<BlockingState>(),
+        Dependency::of::<DetectedRequest>(),
+        Dependency::of::<Control>(),
+        Dependency::of::<Test>(),
+        Dependency::of::<TimerResult>(),
+        Dependency::of::<Status>(),
+        Dependency::of::<MixedEnum>(),
+        Dependency::of::<UnitOnlyEnum>(),
+        Dependency::of::<State>(),
+        Dependency::of::<Lookup>(),
+        Dependency::of::<Sequences>(),
+        Dependency::of::<Priority>(),
+        Dependency::of::<Settings>(),
+        Dependency::of::<Event>(),
+        Dependency::of::<Shape>(),
+        Dependency::of::<Renamed>(),
+        Dependency::of::<Direction>(),
+        Dependency::of::<Message>(),
+        Dependency::of::<Request>(),
+        Dependency::of::<Command>(),
+        Dependency::of::<ConfigValue>(),
+        Dependency::of::<UserId>(),
+        Dependency::of::<Point>(),
+        Dependency::of::<Geometry>(),
+        Dependency::of::<Marker>(),
+        Dependency::of::<Never>(),
+        Dependency::of::<Session>(),
+        Dependency::of::<Login>(),
+        Dependency::of::<Job>(),
+        Dependency::of::<SearchQuery>(),
+        Dependency::of::<Paging>(),
+        Dependency::of::<PagingInput>(),
+        Dependency::of::<Meta>(),
+        Dependency::of::<Document>(),
+        Dependency::of::<Upload>(),
+        Dependency::of::<Attachment>(),
+        Dependency::of::<Primitives>(),
+        Dependency::of::<Borrowed<u8>>(),
+        Dependency::of::<Snapshot>(),
+        Dependency::of::<TreeNode>(),
+        Dependency::of::<Expr>(),
+        Dependency::of::<Tree<()>>(),
+        Dependency::of::<Shared>(),
+        Dependency::of::<Page<()>>(),
+        Dependency::of::<Either<(), ()>>(),
+        Dependency::of::<Feed>(),
+        Dependency::of::<User>(),
+        Dependency::of::<Webhook>(),
+        Dependency::of::<Credentials>(),
+        Dependency::of::<Filter>(),
+        Dependency::of::<Labelled<()>>(),
+        Dependency::of::<Profile>(),
+        Dependency::of::<ProfileInput>(),
+        Dependency::of::<models::Account>(),
+        Dependency::of::<Ledger>(),
+        Dependency::of::<Group>(),
+        Dependency::of::<CategoryNode>(),
+    ]);
+    fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/types.ts"), source).expect("can write");
 }
 
 #[serde_zod::codegen]
@@ -35,7 +92,7 @@ pub enum Control {
 
 #[test]
 fn test_control() {
-    let actual = Control::codegen();
+    let actual = Control::schema_source();
     let expected = r#"export const Control =
   z.discriminatedUnion("kind", [
     z.object({
@@ -54,7 +111,7 @@ fn test_control() {
 }
 
 #[serde_zod::codegen]
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
 pub enum UnitOnlyEnum {
     Stop,
     Toggle,
@@ -68,9 +125,9 @@ pub struct State {
 
 #[test]
 fn test_unit_only_enum() {
-    let actual1 = UnitOnlyEnum::codegen();
-    let actual2 = State::codegen();
-    let joined = vec![actual1, actual2].join("\n");
+    let actual1 = UnitOnlyEnum::schema_source();
+    let actual2 = State::schema_source();
+    let joined = [actual1, actual2].join("\n");
     let expected = r#"export const UnitOnlyEnum =
   z.enum([
     "Stop",
@@ -96,7 +153,7 @@ pub enum Status {
 
 #[test]
 fn test_status() {
-    let actual = Status::codegen();
+    let actual = Status::schema_source();
     let expected = r#"export const Status =
   z.discriminatedUnion("kind", [
     z.object({
@@ -130,7 +187,7 @@ pub enum TimerResult {
 
 #[test]
 fn test_timer_result() {
-    let actual = TimerResult::codegen();
+    let actual = TimerResult::schema_source();
     let expected = r#"export const TimerResult =
   z.discriminatedUnion("kind", [
     z.object({
@@ -146,7 +203,7 @@ fn test_timer_result() {
     }),
     z.object({
       kind: z.literal("WithOptional"),
-      control: Control.optional(),
+      control: Control.nullable(),
     }),
   ])
 "#;
@@ -163,7 +220,7 @@ pub enum Test {
 
 #[test]
 fn test_test() {
-    let actual = Test::codegen();
+    let actual = Test::schema_source();
     let expected = r#"export const Test =
   z.discriminatedUnion("kind", [
     z.object({
@@ -187,7 +244,7 @@ pub enum MixedEnum {
 
 #[test]
 fn test_count_2() {
-    let actual = MixedEnum::codegen();
+    let actual = MixedEnum::schema_source();
     let expected = r#"export const MixedEnum =
   z.union([
     z.literal("One"),
@@ -202,3 +259,1423 @@ fn test_count_2() {
   ])"#;
     assert_eq!(actual, expected);
 }
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Lookup {
+    by_name: HashMap<String, Test>,
+    by_id: BTreeMap<u32, String>,
+    by_offset: std::collections::HashMap<i64, Vec<u8>>,
+    by_control: BTreeMap<UnitOnlyEnum, Option<String>>,
+    by_port: BTreeMap<std::primitive::u16, String>,
+    by_flag: BTreeMap<bool, u8>,
+    by_initial: BTreeMap<char, u8>,
+    by_ratio: BTreeMap<Ratio, u8>,
+    by_step: BTreeMap<std::num::NonZeroI32, u8>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize, PartialEq)]
+pub struct Ratio(f64);
+
+impl Eq for Ratio {}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[test]
+fn test_lookup() {
+    let lookup = Lookup {
+        by_name: HashMap::new(),
+        by_id: BTreeMap::from([(1, "a".into())]),
+        by_offset: HashMap::from([(-1, vec![])]),
+        by_control: BTreeMap::from([(UnitOnlyEnum::Stop, None)]),
+        by_port: BTreeMap::new(),
+        by_flag: BTreeMap::from([(true, 1)]),
+        by_initial: BTreeMap::from([('a', 2)]),
+        by_ratio: BTreeMap::from([(Ratio(0.5), 3), (Ratio(-1e-7), 4)]),
+        by_step: BTreeMap::from([(std::num::NonZeroI32::new(-10).unwrap(), 5)]),
+    };
+    // every key is written as a string matching its schema
+    assert_eq!(
+        serde_json::to_string(&lookup).unwrap(),
+        r#"{"by_name":{},"by_id":{"1":"a"},"by_offset":{"-1":[]},"by_control":{"Stop":null},"by_port":{},"by_flag":{"true":1},"by_initial":{"a":2},"by_ratio":{"-1e-7":4,"0.5":3},"by_step":{"-10":5}}"#
+    );
+    let actual = Lookup::schema_source();
+    let expected = r#"export const Lookup =
+  z.object({
+    by_name: z.record(z.string(), Test),
+    by_id: z.record(z.string().regex(/^\d+$/), z.string()),
+    by_offset: z.record(z.string().regex(/^-?\d+$/), z.array(z.number())),
+    by_control: z.record(UnitOnlyEnum, z.string().nullable()),
+    by_port: z.record(z.string().regex(/^\d+$/), z.string()),
+    by_flag: z.record(z.enum(["true", "false"]), z.number()),
+    by_initial: z.record(z.string().length(1), z.number()),
+    by_ratio: z.record(z.string().regex(/^-?\d+(\.\d+)?(e-?\d+)?$/), z.number()),
+    by_step: z.record(z.string().regex(/^-?[1-9]\d*$/), z.number()),
+  })
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TicketId(u32);
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct OwnerName {
+    name: String,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Tickets {
+    by_id: BTreeMap<TicketId, u8>,
+    by_owner: BTreeMap<OwnerName, Vec<TicketId>>,
+}
+
+#[test]
+fn test_newtype_keys() {
+    // serde_json writes a newtype key as the string form of what it holds
+    let tickets = Tickets {
+        by_id: BTreeMap::from([(TicketId(5), 1)]),
+        by_owner: BTreeMap::from([(OwnerName { name: "ana".into() }, vec![TicketId(5)])]),
+    };
+    assert_eq!(
+        serde_json::to_string(&tickets).unwrap(),
+        r#"{"by_id":{"5":1},"by_owner":{"ana":[5]}}"#
+    );
+    let expected = r#"export const Tickets =
+  z.object({
+    by_id: z.record(z.string().regex(/^\d+$/), z.number()),
+    by_owner: z.record(z.string(), z.array(TicketId)),
+  })
+"#;
+    assert_eq!(Tickets::schema_source(), expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Sequences {
+    tags: HashSet<String>,
+    ids: BTreeSet<u32>,
+    queue: VecDeque<Test>,
+    boxed: Box<[String]>,
+    hash: [u8; 32],
+}
+
+#[test]
+fn test_sequences() {
+    let actual = Sequences::schema_source();
+    let expected = r#"export const Sequences =
+  z.object({
+    tags: z.array(z.string()),
+    ids: z.array(z.number()),
+    queue: z.array(Test),
+    boxed: z.array(z.string()),
+    hash: z.array(z.number()).length(32),
+  })
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Priority {
+    VeryLow,
+    #[default]
+    Normal,
+    High,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    page_url: String,
+    owner_name: Option<String>,
+    priority: Priority,
+}
+
+#[test]
+fn test_rename_all() {
+    let joined = [Priority::schema_source(), Settings::schema_source()].join("\n");
+    let expected = r#"export const Priority =
+  z.enum([
+    "VERY_LOW",
+    "NORMAL",
+    "HIGH",
+  ])
+
+export const Settings =
+  z.object({
+    pageUrl: z.string(),
+    ownerName: z.string().nullable(),
+    priority: Priority,
+  })
+"#;
+    assert_eq!(joined, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum Event {
+    PageLoaded { page_url: String },
+    Closed,
+}
+
+#[test]
+fn test_rename_all_fields() {
+    let actual = Event::schema_source();
+    let expected = r#"export const Event =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("page_loaded"),
+      pageUrl: z.string(),
+    }),
+    z.object({
+      kind: z.literal("closed"),
+    }),
+  ])
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "kebab-case", rename_all_fields = "PascalCase")]
+pub enum Shape {
+    UnitSquare,
+    Circle(f64),
+    RoundedRect { corner_radius: f64 },
+}
+
+#[test]
+fn test_rename_all_mixed() {
+    let actual = Shape::schema_source();
+    let expected = r#"export const Shape =
+  z.union([
+    z.literal("unit-square"),
+    z.object({
+      circle: z.number(),
+    }),
+    z.object({
+      "rounded-rect": z.object({
+        CornerRadius: z.number(),
+      }),
+    }),
+  ])"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum Renamed {
+    #[serde(rename = "start")]
+    Start {
+        #[serde(rename = "type")]
+        kind: String,
+        r#ref: String,
+    },
+    #[serde(rename(serialize = "stopped", deserialize = "stop"))]
+    Stop {
+        #[serde(rename(deserialize = "at"))]
+        time: u32,
+    },
+}
+
+#[test]
+fn test_rename() {
+    let actual = Renamed::schema_source();
+    let expected = r#"export const Renamed =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("start"),
+      type: z.string(),
+      ref: z.string(),
+    }),
+    z.object({
+      kind: z.literal("stopped"),
+      time: z.number(),
+    }),
+  ])
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Deserialize)]
+pub enum Direction {
+    #[serde(alias = "up", alias = "north")]
+    Up,
+    Down,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Deserialize)]
+pub struct Message {
+    #[serde(alias = "href")]
+    pub url: String,
+    pub direction: Direction,
+}
+
+#[test]
+fn test_alias() {
+    let joined = [Direction::schema_source(), Message::schema_source()].join("\n");
+    let expected = r#"export const Direction =
+  z.enum([
+    "Up",
+    "up",
+    "north",
+    "Down",
+  ])
+
+export const Message =
+  z.preprocess(
+    (input: any) => {
+      const aliases: Record<string, string> = { href: "url" }
+      if (input === null || typeof input !== "object" || Array.isArray(input)) return input
+      return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))
+    },
+    z.object({
+      url: z.string(),
+      direction: Direction,
+    })
+  )
+"#;
+    assert_eq!(joined, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum Request {
+    #[serde(alias = "get")]
+    Get {
+        #[serde(alias = "href")]
+        url: String,
+    },
+    Stop,
+}
+
+#[test]
+fn test_alias_tagged() {
+    let actual = Request::schema_source();
+    let expected = r#"export const Request =
+  z.preprocess(
+    (input: any) => {
+      const aliases: Record<string, string> = ({ Get: { href: "url" }, get: { href: "url" } } as Record<string, Record<string, string>>)[input?.["kind"]] ?? {}
+      if (input === null || typeof input !== "object" || Array.isArray(input)) return input
+      return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))
+    },
+    z.discriminatedUnion("kind", [
+      z.object({
+        kind: z.literal("Get"),
+        url: z.string(),
+      }),
+      z.object({
+        kind: z.literal("get"),
+        url: z.string(),
+      }),
+      z.object({
+        kind: z.literal("Stop"),
+      }),
+    ])
+  )
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(tag = "t", content = "c")]
+pub enum Command {
+    Stop,
+    Say(String),
+    Move { x: i32, y: i32 },
+}
+
+#[test]
+fn test_adjacently_tagged() {
+    let actual = Command::schema_source();
+    let expected = r#"export const Command =
+  z.discriminatedUnion("t", [
+    z.object({
+      t: z.literal("Stop"),
+    }),
+    z.object({
+      t: z.literal("Say"),
+      c: z.string(),
+    }),
+    z.object({
+      t: z.literal("Move"),
+      c: z.object({
+        x: z.number(),
+        y: z.number(),
+      }),
+    }),
+  ])
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum ConfigValue {
+    Path(String),
+    Detailed { path: String, optional: Option<u8> },
+    Missing,
+}
+
+#[test]
+fn test_untagged() {
+    let actual = ConfigValue::schema_source();
+    let expected = r#"export const ConfigValue =
+  z.union([
+    z.string(),
+    z.object({
+      path: z.string(),
+      optional: z.number().nullable(),
+    }),
+    z.null(),
+  ])
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct UserId(String);
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Point(f64, f64);
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub enum Geometry {
+    Point(f64, f64),
+    Labelled {
+        at: (f64, f64),
+        label: (String, UserId),
+    },
+}
+
+#[test]
+fn test_tuples() {
+    let joined = [
+        UserId::schema_source(),
+        Point::schema_source(),
+        Geometry::schema_source(),
+    ]
+    .join("\n");
+    let expected = r#"export const UserId =
+  z.string()
+
+export const Point =
+  z.tuple([z.number(), z.number()])
+
+export const Geometry =
+  z.union([
+    z.object({
+      Point: z.tuple([z.number(), z.number()]),
+    }),
+    z.object({
+      Labelled: z.object({
+        at: z.tuple([z.number(), z.number()]),
+        label: z.tuple([z.string(), UserId]),
+      }),
+    }),
+  ])"#;
+    assert_eq!(joined, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Sparse(pub u8, #[serde(skip)] pub u8, pub u8);
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub enum SparseVariant {
+    Pair(u8, #[serde(skip)] u8),
+}
+
+#[test]
+fn test_skipped_unnamed_fields() {
+    // serde still writes an array when all but one field is skipped
+    assert_eq!(serde_json::to_string(&Sparse(1, 2, 3)).unwrap(), "[1,3]");
+    assert_eq!(
+        serde_json::to_string(&SparseVariant::Pair(1, 2)).unwrap(),
+        r#"{"Pair":[1]}"#
+    );
+    let expected = r#"export const Sparse =
+  z.tuple([z.number(), z.number()])
+"#;
+    assert_eq!(Sparse::schema_source(), expected);
+    let expected = r#"export const SparseVariant =
+  z.object({
+    Pair: z.tuple([z.number()]),
+  })"#;
+    assert_eq!(SparseVariant::schema_source(), expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Marker;
+
+#[test]
+fn test_unit_struct() {
+    let actual = Marker::schema_source();
+    let expected = r#"export const Marker =
+  z.null()
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub enum Never {}
+
+#[test]
+fn test_empty_enum() {
+    let actual = Never::schema_source();
+    let expected = r#"export const Never =
+  z.never()
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Session {
+    pub id: String,
+    #[serde(skip)]
+    pub cache: Vec<u8>,
+    #[serde(skip_serializing)]
+    pub password: String,
+    #[serde(skip_deserializing)]
+    pub created: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Deserialize)]
+pub struct Login {
+    #[serde(skip)]
+    pub cache: Vec<u8>,
+    #[serde(skip_serializing)]
+    pub password: String,
+    #[serde(skip_deserializing)]
+    pub created: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+#[test]
+fn test_skip_fields() {
+    let joined = [Session::schema_source(), Login::schema_source()].join("\n");
+    let expected = r#"export const Session =
+  z.object({
+    id: z.string(),
+    created: z.number(),
+    roles: z.array(z.string()).optional(),
+  })
+
+export const Login =
+  z.object({
+    password: z.string(),
+    roles: z.array(z.string()),
+  })
+"#;
+    assert_eq!(joined, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(tag = "kind")]
+pub enum Job {
+    Queued,
+    #[serde(skip_serializing)]
+    Internal {
+        pid: u32,
+    },
+    Done {
+        code: u8,
+    },
+}
+
+#[test]
+fn test_skip_variants() {
+    let actual = Job::schema_source();
+    let expected = r#"export const Job =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("Queued"),
+    }),
+    z.object({
+      kind: z.literal("Done"),
+      code: z.number(),
+    }),
+  ])
+"#;
+    assert_eq!(actual, expected);
+}
+
+fn default_limit() -> u32 {
+    20
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Deserialize)]
+pub struct SearchQuery {
+    pub term: String,
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+#[test]
+fn test_field_default() {
+    let actual = SearchQuery::schema_source();
+    let expected = r#"export const SearchQuery =
+  z.object({
+    term: z.string(),
+    page: z.number().default(0),
+    tags: z.array(z.string()).default([]),
+    limit: z.number().optional(),
+    priority: Priority.optional(),
+  })
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Paging {
+    page: u32,
+    size: u32,
+}
+
+#[serde_zod::codegen]
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct PagingInput {
+    pub page: u32,
+    #[serde(default)]
+    pub size: u32,
+}
+
+#[test]
+fn test_container_default() {
+    let joined = [Paging::schema_source(), PagingInput::schema_source()].join("\n");
+    let expected = r#"export const Paging =
+  z.object({
+    page: z.number(),
+    size: z.number(),
+  })
+
+export const PagingInput =
+  z.object({
+    page: z.number().optional(),
+    size: z.number().default(0),
+  })
+"#;
+    assert_eq!(joined, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Meta {
+    created: u64,
+    author: String,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Document {
+    id: String,
+    #[serde(flatten)]
+    meta: Meta,
+    #[serde(flatten)]
+    extra: HashMap<String, u32>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(tag = "kind")]
+pub enum Upload {
+    Pending,
+    Complete {
+        url: String,
+        #[serde(flatten)]
+        meta: Meta,
+    },
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Preferences {
+    theme: String,
+    #[serde(flatten)]
+    overrides: Option<HashMap<String, bool>>,
+}
+
+#[test]
+fn test_flatten() {
+    let joined = [
+        Document::schema_source(),
+        Upload::schema_source(),
+        Preferences::schema_source(),
+    ]
+    .join("\n");
+    let expected = r#"export const Document =
+  z.object({
+    id: z.string(),
+  }).merge(Meta).catchall(z.number())
+
+export const Upload =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("Pending"),
+    }),
+    z.object({
+      kind: z.literal("Complete"),
+      url: z.string(),
+    }).merge(Meta),
+  ])
+
+export const Preferences =
+  z.object({
+    theme: z.string(),
+  }).catchall(z.boolean())
+"#;
+    assert_eq!(joined, expected);
+    // only plain objects can be merged into another schema
+    fn object<T: serde_zod_runtime::ZodObjectSchema>() {}
+    object::<Meta>();
+    object::<Document>();
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(tag = "kind")]
+pub enum Attachment {
+    Document(Meta),
+    Labels(BTreeMap<String, String>),
+    Cleared(()),
+}
+
+#[test]
+fn test_tagged_newtype() {
+    // serde writes the payload's fields beside the tag
+    let document = Attachment::Document(Meta {
+        created: 1,
+        author: "ana".into(),
+    });
+    let labels = Attachment::Labels(BTreeMap::from([("lang".into(), "en".into())]));
+    let written = [document, labels, Attachment::Cleared(())]
+        .iter()
+        .map(|attachment| serde_json::to_string(attachment).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        written,
+        [
+            r#"{"kind":"Document","created":1,"author":"ana"}"#,
+            r#"{"kind":"Labels","lang":"en"}"#,
+            r#"{"kind":"Cleared"}"#,
+        ]
+    );
+    let expected = r#"export const Attachment =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("Document"),
+    }).merge(Meta),
+    z.object({
+      kind: z.literal("Labels"),
+    }).catchall(z.string()),
+    z.object({
+      kind: z.literal("Cleared"),
+    }),
+  ])
+"#;
+    assert_eq!(Attachment::schema_source(), expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Primitives<'a> {
+    enabled: bool,
+    small: u16,
+    signed: i16,
+    large: u128,
+    negative: i128,
+    initial: char,
+    borrowed: &'a str,
+    cow: Cow<'a, str>,
+    boxed: Box<str>,
+    nothing: (),
+}
+
+#[test]
+fn test_primitives() {
+    let actual = Primitives::schema_source();
+    let expected = r#"export const Primitives =
+  z.object({
+    enabled: z.boolean(),
+    small: z.number(),
+    signed: z.number(),
+    large: z.number(),
+    negative: z.number(),
+    initial: z.string().length(1),
+    borrowed: z.string(),
+    cow: z.string(),
+    boxed: z.string(),
+    nothing: z.null(),
+  })
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Shared {
+    boxed: Box<Meta>,
+    counted: Rc<String>,
+    atomic: Arc<Vec<u8>>,
+    cell: RefCell<bool>,
+    locked: Mutex<Option<String>>,
+    marker: PhantomData<Meta>,
+}
+
+#[test]
+fn test_wrappers() {
+    let actual = Shared::schema_source();
+    let expected = r#"export const Shared =
+  z.object({
+    boxed: Meta,
+    counted: z.string(),
+    atomic: z.array(z.number()),
+    cell: z.boolean(),
+    locked: z.string().nullable(),
+    marker: z.null(),
+  })
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Page<T> {
+    items: Vec<T>,
+    total: u32,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Feed {
+    page: Page<Meta>,
+    latest: Either<Meta, String>,
+}
+
+#[test]
+fn test_generics() {
+    let page = r#"export const Page = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    items: z.array(T),
+    total: z.number(),
+  })
+"#;
+    assert_eq!(Page::<Meta>::schema_source(), page);
+    let either = r#"export const Either = <L extends z.ZodTypeAny, R extends z.ZodTypeAny>(L: L, R: R) =>
+  z.union([
+    z.object({
+      Left: L,
+    }),
+    z.object({
+      Right: R,
+    }),
+  ])"#;
+    assert_eq!(Either::<Meta, Meta>::schema_source(), either);
+    let feed = r#"export const Feed =
+  z.object({
+    page: Page(Meta),
+    latest: Either(Meta, z.string()),
+  })
+"#;
+    assert_eq!(Feed::schema_source(), feed);
+}
+
+#[serde_zod::codegen(name = "UserDto", strict, type_alias)]
+#[derive(serde::Serialize)]
+pub struct User {
+    id: u32,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+#[serde_zod::codegen(input, passthrough)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Webhook {
+    Created { id: u32 },
+    Deleted,
+}
+
+#[serde_zod::codegen(type_alias)]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    #[serde(alias = "user")]
+    pub username: String,
+    pub password: String,
+}
+
+#[test]
+fn test_codegen_args() {
+    let user = r#"export const UserDto =
+  z.object({
+    id: z.number(),
+  }).merge(Meta).strict()
+export type UserDto = z.infer<typeof UserDto>;
+"#;
+    assert_eq!(User::schema_source(), user);
+    let webhook = r#"export const Webhook =
+  z.discriminatedUnion("type", [
+    z.object({
+      type: z.literal("Created"),
+      id: z.number(),
+    }).passthrough(),
+    z.object({
+      type: z.literal("Deleted"),
+    }).passthrough(),
+  ])
+"#;
+    assert_eq!(Webhook::schema_source(), webhook);
+    let credentials = r#"export const Credentials =
+  z.preprocess(
+    (input: any) => {
+      const aliases: Record<string, string> = { user: "username" }
+      if (input === null || typeof input !== "object" || Array.isArray(input)) return input
+      return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))
+    },
+    z.object({
+      username: z.string(),
+      password: z.string(),
+    }).strict()
+  )
+export type Credentials = z.infer<typeof Credentials>;
+export type CredentialsInput = z.input<typeof Credentials>;
+export type CredentialsOutput = z.output<typeof Credentials>;
+"#;
+    assert_eq!(Credentials::schema_source(), credentials);
+}
+
+#[serde_zod::codegen(type_alias)]
+#[derive(serde::Deserialize)]
+pub struct Filter {
+    #[serde(default)]
+    pub limit: u32,
+    pub query: String,
+}
+
+#[serde_zod::codegen(type_alias)]
+#[derive(serde::Serialize)]
+pub struct Labelled<T> {
+    label: String,
+    value: T,
+}
+
+#[test]
+fn test_type_aliases() {
+    let filter = r#"export const Filter =
+  z.object({
+    limit: z.number().default(0),
+    query: z.string(),
+  })
+export type Filter = z.infer<typeof Filter>;
+export type FilterInput = z.input<typeof Filter>;
+export type FilterOutput = z.output<typeof Filter>;
+"#;
+    assert_eq!(Filter::schema_source(), filter);
+    let labelled = r#"export const Labelled = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    label: z.string(),
+    value: T,
+  })
+export type Labelled<T extends z.ZodTypeAny> = z.infer<ReturnType<typeof Labelled<T>>>;
+"#;
+    assert_eq!(Labelled::<u8>::schema_source(), labelled);
+}
+
+#[cfg(test)]
+fn export<T: ZodSchema>() -> String {
+    format!("{}\n{}", T::imports(), T::schema_source())
+}
+
+#[test]
+fn test_zod_schema() {
+    assert_eq!(User::schema_name(), "UserDto");
+    assert!(export::<Meta>().starts_with("import z from \"zod\";\n"));
+    let names = |deps: Vec<serde_zod_runtime::Dependency>| {
+        deps.iter().map(|dep| dep.schema_name()).collect::<Vec<_>>()
+    };
+    assert_eq!(names(Feed::dependencies()), ["Page", "Meta", "Either"]);
+    // type parameters are passed in, rather than depended on
+    assert!(Page::<Meta>::dependencies().is_empty());
+    assert_eq!(names(Settings::dependencies()), ["Priority"]);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Profile {
+    nickname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avatar: Option<String>,
+    #[serde(skip_serializing_if = "is_blank")]
+    bio: Option<String>,
+    history: Vec<Option<u32>>,
+    #[serde(flatten)]
+    meta: Option<Meta>,
+}
+
+fn is_blank(bio: &Option<String>) -> bool {
+    bio.as_deref().map_or(true, str::is_empty)
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Deserialize)]
+pub struct ProfileInput {
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    pub history: Vec<Option<u32>>,
+}
+
+#[test]
+fn test_options() {
+    let profile = r#"export const Profile =
+  z.object({
+    nickname: z.string().nullable(),
+    avatar: z.string().optional(),
+    bio: z.string().nullish(),
+    history: z.array(z.number().nullable()),
+  }).merge(Meta.partial())
+"#;
+    assert_eq!(Profile::schema_source(), profile);
+    let input = r#"export const ProfileInput =
+  z.object({
+    nickname: z.string().nullish(),
+    avatar: z.string().nullish(),
+    history: z.array(z.number().nullable()),
+  })
+"#;
+    assert_eq!(ProfileInput::schema_source(), input);
+}
+
+mod models {
+    #[serde_zod::codegen]
+    #[derive(serde::Serialize)]
+    pub struct Account {
+        pub id: u32,
+    }
+
+    #[serde_zod::codegen(name = "AccountGroup")]
+    #[derive(serde::Serialize)]
+    pub struct Group {
+        pub accounts: Vec<Account>,
+    }
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Group {
+    accounts: models::Group,
+    subgroups: Vec<Group>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Ledger {
+    owner: crate::models::Account,
+    accounts: std::vec::Vec<self::models::Account>,
+    label: std::option::Option<std::string::String>,
+    totals: std::collections::BTreeMap<std::string::String, u64>,
+}
+
+#[serde_zod::codegen(name = "Category")]
+#[derive(serde::Serialize)]
+pub struct CategoryNode {
+    label: String,
+    parent: Option<Box<Self>>,
+    children: Vec<CategoryNode>,
+}
+
+#[test]
+fn test_paths() {
+    let ledger = r#"export const Ledger =
+  z.object({
+    owner: Account,
+    accounts: z.array(Account),
+    label: z.string().nullable(),
+    totals: z.record(z.string(), z.number()),
+  })
+"#;
+    assert_eq!(Ledger::schema_source(), ledger);
+    let category = r#"export type Category = { label: string; parent: Category | null; children: Array<Category> };
+export const Category: z.ZodType<Category> =
+  z.object({
+    label: z.string(),
+    parent: z.lazy(() => Category).nullable(),
+    children: z.array(z.lazy(() => Category)),
+  })
+"#;
+    assert_eq!(CategoryNode::schema_source(), category);
+    // only an unqualified path refers to the type being described
+    let group = r#"export type Group = { accounts: z.infer<typeof AccountGroup>; subgroups: Array<Group> };
+export const Group: z.ZodType<Group> =
+  z.object({
+    accounts: AccountGroup,
+    subgroups: z.array(z.lazy(() => Group)),
+  })
+"#;
+    assert_eq!(Group::schema_source(), group);
+    let bundled = bundle(&[Dependency::of::<Group>()]);
+    let expected = format!(
+        "{}\n{}\n{}\n{}",
+        Group::imports(),
+        models::Account::schema_source(),
+        models::Group::schema_source(),
+        group
+    );
+    assert_eq!(bundled, expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Borrowed<'a, T: Clone> {
+    name: &'a str,
+    ids: &'a [u32],
+    values: Cow<'a, [T]>,
+    meta: &'a Meta,
+    #[allow(unused_parens)]
+    nested: (&'a str),
+    primitives: Primitives<'a>,
+}
+
+macro_rules! snapshot {
+    ($ty:ty) => {
+        #[serde_zod::codegen]
+        #[derive(serde::Serialize)]
+        pub struct Snapshot {
+            value: $ty,
+        }
+    };
+}
+
+snapshot!(Vec<u8>);
+
+#[test]
+fn test_borrowed() {
+    let borrowed = r#"export const Borrowed = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    name: z.string(),
+    ids: z.array(z.number()),
+    values: z.array(T),
+    meta: Meta,
+    nested: z.string(),
+    primitives: Primitives,
+  })
+"#;
+    assert_eq!(Borrowed::<u8>::schema_source(), borrowed);
+    let snapshot = r#"export const Snapshot =
+  z.object({
+    value: z.array(z.number()),
+  })
+"#;
+    assert_eq!(Snapshot::schema_source(), snapshot);
+}
+
+#[serde_zod::codegen(type_alias)]
+#[derive(serde::Serialize)]
+pub struct TreeNode {
+    label: String,
+    children: Vec<TreeNode>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(tag = "op")]
+pub enum Expr {
+    Num { value: f64 },
+    Add { left: Box<Expr>, right: Box<Expr> },
+    Neg { expr: Box<Self> },
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Tree<T> {
+    value: T,
+    children: Vec<Tree<T>>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Forest<T> {
+    trees: Vec<T>,
+    groves: Vec<Self>,
+}
+
+#[test]
+fn test_recursive() {
+    let tree_node = r#"export type TreeNode = { label: string; children: Array<TreeNode> };
+export const TreeNode: z.ZodType<TreeNode> =
+  z.object({
+    label: z.string(),
+    children: z.array(z.lazy(() => TreeNode)),
+  })
+"#;
+    assert_eq!(TreeNode::schema_source(), tree_node);
+    let expr = r#"export type Expr = { op: "Num"; value: number } | { op: "Add"; left: Expr; right: Expr } | { op: "Neg"; expr: Expr };
+export const Expr: z.ZodType<Expr> =
+  z.discriminatedUnion("op", [
+    z.object({
+      op: z.literal("Num"),
+      value: z.number(),
+    }),
+    z.object({
+      op: z.literal("Add"),
+      left: z.lazy(() => Expr),
+      right: z.lazy(() => Expr),
+    }),
+    z.object({
+      op: z.literal("Neg"),
+      expr: z.lazy(() => Expr),
+    }),
+  ])
+"#;
+    assert_eq!(Expr::schema_source(), expr);
+    let tree = r#"export const Tree = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    value: T,
+    children: z.array(z.lazy((): z.ZodTypeAny => Tree(T))),
+  })
+"#;
+    assert_eq!(Tree::<u8>::schema_source(), tree);
+    let forest = r#"export const Forest = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    trees: z.array(T),
+    groves: z.array(z.lazy((): z.ZodTypeAny => Forest(T))),
+  })
+"#;
+    assert_eq!(Forest::<u8>::schema_source(), forest);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Folder {
+    name: String,
+    files: Vec<File>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct File {
+    name: String,
+    parent: Option<Box<Folder>>,
+}
+
+#[test]
+fn test_bundle() {
+    let actual = bundle(&[Dependency::of::<TimerResult>()]);
+    assert!(actual.starts_with("import z from \"zod\";\n\n"));
+    let position = |name: &str| actual.find(&format!("export const {} =", name)).unwrap();
+    assert!(position("Test") < position("TimerResult"));
+    assert!(position("Control") < position("TimerResult"));
+    // `TimerResult` is only declared once, before `Status` refers to it
+    let actual = bundle(&[Dependency::of::<Status>(), Dependency::of::<TimerResult>()]);
+    assert_eq!(actual.matches("export const TimerResult =").count(), 1);
+    assert!(actual.ends_with(&Status::schema_source()));
+}
+
+#[test]
+fn test_bundle_cycle() {
+    let expected = r#"import z from "zod";
+
+export const File =
+  z.object({
+    name: z.string(),
+    parent: z.lazy((): z.ZodTypeAny => Folder).nullable(),
+  })
+
+export const Folder =
+  z.object({
+    name: z.string(),
+    files: z.array(File),
+  })
+"#;
+    assert_eq!(bundle(&[Dependency::of::<Folder>()]), expected);
+    // on its own, `File` refers to `Folder` as usual
+    assert!(File::schema_source().contains("parent: Folder.nullable(),"));
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Article {
+    title: String,
+    #[serde(flatten)]
+    byline: Byline,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Byline {
+    author: String,
+    article: Option<Box<Article>>,
+}
+
+#[test]
+fn test_bundle_flatten_cycle() {
+    let expected = r#"import z from "zod";
+
+export const Byline =
+  z.object({
+    author: z.string(),
+    article: z.lazy((): z.ZodTypeAny => Article).nullable(),
+  })
+
+export const Article =
+  z.object({
+    title: z.string(),
+  }).merge(Byline)
+"#;
+    // `.merge()` takes `Byline` itself, so it comes first even where depth first would put it last
+    assert_eq!(bundle(&[Dependency::of::<Byline>()]), expected);
+    assert_eq!(bundle(&[Dependency::of::<Article>()]), expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(transparent)]
+pub struct Email {
+    pub address: String,
+    #[serde(skip)]
+    pub verified: bool,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename = "circle")]
+pub struct Circle {
+    radius: f64,
+}
+
+#[test]
+fn test_container_shape() {
+    let email = Email {
+        address: "a@b.c".into(),
+        verified: true,
+    };
+    assert_eq!(serde_json::to_string(&email).unwrap(), r#""a@b.c""#);
+    let expected = r#"export const Email =
+  z.string()
+"#;
+    assert_eq!(Email::schema_source(), expected);
+    let circle = Circle { radius: 1.0 };
+    assert_eq!(
+        serde_json::to_string(&circle).unwrap(),
+        r#"{"type":"circle","radius":1.0}"#
+    );
+    let expected = r#"export const Circle =
+  z.object({
+    type: z.literal("circle"),
+    radius: z.number(),
+  })
+"#;
+    assert_eq!(Circle::schema_source(), expected);
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Team {
+    owner: User,
+    members: Vec<User>,
+    parent: Option<Box<Team>>,
+}
+
+#[test]
+fn test_renamed_reference() {
+    let expected = r#"export type Team = { owner: z.infer<typeof UserDto>; members: Array<z.infer<typeof UserDto>>; parent: Team | null };
+export const Team: z.ZodType<Team> =
+  z.object({
+    owner: UserDto,
+    members: z.array(UserDto),
+    parent: z.lazy(() => Team).nullable(),
+  })
+"#;
+    assert_eq!(Team::schema_source(), expected);
+    let bundled = bundle(&[Dependency::of::<Team>()]);
+    assert!(bundled.contains("export const UserDto ="));
+    assert!(!bundled.contains("export const User ="));
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct AuditEntry {
+    #[serde_zod(schema = "z.record(z.string(), z.unknown())")]
+    payload: serde_json::Value,
+    tags: Vec<String>,
+}
+
+#[serde_zod::codegen]
+#[derive(serde::Serialize)]
+pub struct Host(#[serde_zod(schema = "z.string().ip()")] std::net::IpAddr);
+
+#[test]
+fn test_custom_schema() {
+    // types from other crates have no `ZodSchema`, so the field describes itself
+    let expected = r#"export const AuditEntry =
+  z.object({
+    payload: z.record(z.string(), z.unknown()),
+    tags: z.array(z.string()),
+  })
+"#;
+    assert_eq!(AuditEntry::schema_source(), expected);
+    assert!(AuditEntry::dependencies().is_empty());
+    let expected = r#"export const Host =
+  z.string().ip()
+"#;
+    assert_eq!(Host::schema_source(), expected);
+}
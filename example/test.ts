@@ -1,4 +1,4 @@
-import {BlockingState, Control, MixedEnum, State, Test} from "./types";
+import {Attachment, BlockingState, Control, MixedEnum, State, Test} from "./types";
 
 const bs = BlockingState.parse({
   kind: "Allowed",
@@ -10,6 +10,11 @@ MixedEnum.parse({Two: "hello...."})
 MixedEnum.parse({Three: { temp: 3 }})
 
 
+// as written by serde_json, see `test_tagged_newtype`
+Attachment.parse({"kind":"Document","created":1,"author":"ana"})
+Attachment.parse({"kind":"Labels","lang":"en"})
+Attachment.parse({"kind":"Cleared"})
+
 const message = Control.parse({ kind: "Start", time: 10 });
 if (message.kind === "Start") {
   console.log(message.time) // Type-safe property access
@@ -24,10 +24,10 @@ export const DetectedRequest =
   z.object({
     url: z.string(),
     state: BlockingState,
-    owner_name: z.string().optional(),
-    entity_name: z.string().optional(),
-    category: z.string().optional(),
-    prevalence: z.number().optional(),
+    owner_name: z.string().nullable(),
+    entity_name: z.string().nullable(),
+    category: z.string().nullable(),
+    prevalence: z.number().nullable(),
     page_url: z.string(),
   })
 
@@ -70,7 +70,7 @@ export const TimerResult =
     }),
     z.object({
       kind: z.literal("WithOptional"),
-      control: Control.optional(),
+      control: Control.nullable(),
     }),
   ])
 
@@ -114,3 +114,441 @@ export const State =
   z.object({
     control: UnitOnlyEnum,
   })
+
+export const Ratio =
+  z.number()
+
+export const Lookup =
+  z.object({
+    by_name: z.record(z.string(), Test),
+    by_id: z.record(z.string().regex(/^\d+$/), z.string()),
+    by_offset: z.record(z.string().regex(/^-?\d+$/), z.array(z.number())),
+    by_control: z.record(UnitOnlyEnum, z.string().nullable()),
+    by_port: z.record(z.string().regex(/^\d+$/), z.string()),
+    by_flag: z.record(z.enum(["true", "false"]), z.number()),
+    by_initial: z.record(z.string().length(1), z.number()),
+    by_ratio: z.record(z.string().regex(/^-?\d+(\.\d+)?(e-?\d+)?$/), z.number()),
+    by_step: z.record(z.string().regex(/^-?[1-9]\d*$/), z.number()),
+  })
+
+export const Sequences =
+  z.object({
+    tags: z.array(z.string()),
+    ids: z.array(z.number()),
+    queue: z.array(Test),
+    boxed: z.array(z.string()),
+    hash: z.array(z.number()).length(32),
+  })
+
+export const Priority =
+  z.enum([
+    "VERY_LOW",
+    "NORMAL",
+    "HIGH",
+  ])
+
+export const Settings =
+  z.object({
+    pageUrl: z.string(),
+    ownerName: z.string().nullable(),
+    priority: Priority,
+  })
+
+export const Event =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("page_loaded"),
+      pageUrl: z.string(),
+    }),
+    z.object({
+      kind: z.literal("closed"),
+    }),
+  ])
+
+export const Shape =
+  z.union([
+    z.literal("unit-square"),
+    z.object({
+      circle: z.number(),
+    }),
+    z.object({
+      "rounded-rect": z.object({
+        CornerRadius: z.number(),
+      }),
+    }),
+  ])
+export const Renamed =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("start"),
+      type: z.string(),
+      ref: z.string(),
+    }),
+    z.object({
+      kind: z.literal("stopped"),
+      time: z.number(),
+    }),
+  ])
+
+export const Direction =
+  z.enum([
+    "Up",
+    "up",
+    "north",
+    "Down",
+  ])
+
+export const Message =
+  z.preprocess(
+    (input: any) => {
+      const aliases: Record<string, string> = { href: "url" }
+      if (input === null || typeof input !== "object" || Array.isArray(input)) return input
+      return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))
+    },
+    z.object({
+      url: z.string(),
+      direction: Direction,
+    })
+  )
+
+export const Request =
+  z.preprocess(
+    (input: any) => {
+      const aliases: Record<string, string> = ({ Get: { href: "url" }, get: { href: "url" } } as Record<string, Record<string, string>>)[input?.["kind"]] ?? {}
+      if (input === null || typeof input !== "object" || Array.isArray(input)) return input
+      return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))
+    },
+    z.discriminatedUnion("kind", [
+      z.object({
+        kind: z.literal("Get"),
+        url: z.string(),
+      }),
+      z.object({
+        kind: z.literal("get"),
+        url: z.string(),
+      }),
+      z.object({
+        kind: z.literal("Stop"),
+      }),
+    ])
+  )
+
+export const Command =
+  z.discriminatedUnion("t", [
+    z.object({
+      t: z.literal("Stop"),
+    }),
+    z.object({
+      t: z.literal("Say"),
+      c: z.string(),
+    }),
+    z.object({
+      t: z.literal("Move"),
+      c: z.object({
+        x: z.number(),
+        y: z.number(),
+      }),
+    }),
+  ])
+
+export const ConfigValue =
+  z.union([
+    z.string(),
+    z.object({
+      path: z.string(),
+      optional: z.number().nullable(),
+    }),
+    z.null(),
+  ])
+
+export const UserId =
+  z.string()
+
+export const Point =
+  z.tuple([z.number(), z.number()])
+
+export const Geometry =
+  z.union([
+    z.object({
+      Point: z.tuple([z.number(), z.number()]),
+    }),
+    z.object({
+      Labelled: z.object({
+        at: z.tuple([z.number(), z.number()]),
+        label: z.tuple([z.string(), UserId]),
+      }),
+    }),
+  ])
+export const Marker =
+  z.null()
+
+export const Never =
+  z.never()
+
+export const Session =
+  z.object({
+    id: z.string(),
+    created: z.number(),
+    roles: z.array(z.string()).optional(),
+  })
+
+export const Login =
+  z.object({
+    password: z.string(),
+    roles: z.array(z.string()),
+  })
+
+export const Job =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("Queued"),
+    }),
+    z.object({
+      kind: z.literal("Done"),
+      code: z.number(),
+    }),
+  ])
+
+export const SearchQuery =
+  z.object({
+    term: z.string(),
+    page: z.number().default(0),
+    tags: z.array(z.string()).default([]),
+    limit: z.number().optional(),
+    priority: Priority.optional(),
+  })
+
+export const Paging =
+  z.object({
+    page: z.number(),
+    size: z.number(),
+  })
+
+export const PagingInput =
+  z.object({
+    page: z.number().optional(),
+    size: z.number().default(0),
+  })
+
+export const Meta =
+  z.object({
+    created: z.number(),
+    author: z.string(),
+  })
+
+export const Document =
+  z.object({
+    id: z.string(),
+  }).merge(Meta).catchall(z.number())
+
+export const Upload =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("Pending"),
+    }),
+    z.object({
+      kind: z.literal("Complete"),
+      url: z.string(),
+    }).merge(Meta),
+  ])
+
+export const Attachment =
+  z.discriminatedUnion("kind", [
+    z.object({
+      kind: z.literal("Document"),
+    }).merge(Meta),
+    z.object({
+      kind: z.literal("Labels"),
+    }).catchall(z.string()),
+    z.object({
+      kind: z.literal("Cleared"),
+    }),
+  ])
+
+export const Primitives =
+  z.object({
+    enabled: z.boolean(),
+    small: z.number(),
+    signed: z.number(),
+    large: z.number(),
+    negative: z.number(),
+    initial: z.string().length(1),
+    borrowed: z.string(),
+    cow: z.string(),
+    boxed: z.string(),
+    nothing: z.null(),
+  })
+
+export const Borrowed = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    name: z.string(),
+    ids: z.array(z.number()),
+    values: z.array(T),
+    meta: Meta,
+    nested: z.string(),
+    primitives: Primitives,
+  })
+
+export const Snapshot =
+  z.object({
+    value: z.array(z.number()),
+  })
+
+export type TreeNode = { label: string; children: Array<TreeNode> };
+export const TreeNode: z.ZodType<TreeNode> =
+  z.object({
+    label: z.string(),
+    children: z.array(z.lazy(() => TreeNode)),
+  })
+
+export type Expr = { op: "Num"; value: number } | { op: "Add"; left: Expr; right: Expr } | { op: "Neg"; expr: Expr };
+export const Expr: z.ZodType<Expr> =
+  z.discriminatedUnion("op", [
+    z.object({
+      op: z.literal("Num"),
+      value: z.number(),
+    }),
+    z.object({
+      op: z.literal("Add"),
+      left: z.lazy(() => Expr),
+      right: z.lazy(() => Expr),
+    }),
+    z.object({
+      op: z.literal("Neg"),
+      expr: z.lazy(() => Expr),
+    }),
+  ])
+
+export const Tree = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    value: T,
+    children: z.array(z.lazy((): z.ZodTypeAny => Tree(T))),
+  })
+
+export const Shared =
+  z.object({
+    boxed: Meta,
+    counted: z.string(),
+    atomic: z.array(z.number()),
+    cell: z.boolean(),
+    locked: z.string().nullable(),
+    marker: z.null(),
+  })
+
+export const Page = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    items: z.array(T),
+    total: z.number(),
+  })
+
+export const Either = <L extends z.ZodTypeAny, R extends z.ZodTypeAny>(L: L, R: R) =>
+  z.union([
+    z.object({
+      Left: L,
+    }),
+    z.object({
+      Right: R,
+    }),
+  ])
+export const Feed =
+  z.object({
+    page: Page(Meta),
+    latest: Either(Meta, z.string()),
+  })
+
+export const UserDto =
+  z.object({
+    id: z.number(),
+  }).merge(Meta).strict()
+export type UserDto = z.infer<typeof UserDto>;
+
+export const Webhook =
+  z.discriminatedUnion("type", [
+    z.object({
+      type: z.literal("Created"),
+      id: z.number(),
+    }).passthrough(),
+    z.object({
+      type: z.literal("Deleted"),
+    }).passthrough(),
+  ])
+
+export const Credentials =
+  z.preprocess(
+    (input: any) => {
+      const aliases: Record<string, string> = { user: "username" }
+      if (input === null || typeof input !== "object" || Array.isArray(input)) return input
+      return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))
+    },
+    z.object({
+      username: z.string(),
+      password: z.string(),
+    }).strict()
+  )
+export type Credentials = z.infer<typeof Credentials>;
+export type CredentialsInput = z.input<typeof Credentials>;
+export type CredentialsOutput = z.output<typeof Credentials>;
+
+export const Filter =
+  z.object({
+    limit: z.number().default(0),
+    query: z.string(),
+  })
+export type Filter = z.infer<typeof Filter>;
+export type FilterInput = z.input<typeof Filter>;
+export type FilterOutput = z.output<typeof Filter>;
+
+export const Labelled = <T extends z.ZodTypeAny>(T: T) =>
+  z.object({
+    label: z.string(),
+    value: T,
+  })
+export type Labelled<T extends z.ZodTypeAny> = z.infer<ReturnType<typeof Labelled<T>>>;
+
+export const Profile =
+  z.object({
+    nickname: z.string().nullable(),
+    avatar: z.string().optional(),
+    bio: z.string().nullish(),
+    history: z.array(z.number().nullable()),
+  }).merge(Meta.partial())
+
+export const ProfileInput =
+  z.object({
+    nickname: z.string().nullish(),
+    avatar: z.string().nullish(),
+    history: z.array(z.number().nullable()),
+  })
+
+export const Account =
+  z.object({
+    id: z.number(),
+  })
+
+export const Ledger =
+  z.object({
+    owner: Account,
+    accounts: z.array(Account),
+    label: z.string().nullable(),
+    totals: z.record(z.string(), z.number()),
+  })
+
+export const AccountGroup =
+  z.object({
+    accounts: z.array(Account),
+  })
+
+export type Group = { accounts: z.infer<typeof AccountGroup>; subgroups: Array<Group> };
+export const Group: z.ZodType<Group> =
+  z.object({
+    accounts: AccountGroup,
+    subgroups: z.array(z.lazy(() => Group)),
+  })
+
+export type Category = { label: string; parent: Category | null; children: Array<Category> };
+export const Category: z.ZodType<Category> =
+  z.object({
+    label: z.string(),
+    parent: z.lazy(() => Category).nullable(),
+    children: z.array(z.lazy(() => Category)),
+  })
@@ -0,0 +1,209 @@
+//! The traits implemented by `#[serde_zod::codegen]`, so that tools can work with the
+//! generated schemas generically, for example
+//!
+//! ```ignore
+//! use serde_zod_runtime::ZodSchema;
+//!
+//! fn export<T: ZodSchema>() -> String {
+//!     format!("{}\n{}", T::imports(), T::schema_source())
+//! }
+//! ```
+//!
+//! or, with every schema they depend on declared before them, with [`bundle`]
+//!
+//! ```ignore
+//! use serde_zod_runtime::{bundle, Dependency};
+//!
+//! let source = bundle(&[Dependency::of::<User>(), Dependency::of::<Session>()]);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+/// A Rust type with a zod schema, implemented by `#[serde_zod::codegen]`
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no zod schema",
+    note = "add `#[serde_zod::codegen]` to `{Self}`, or describe the field with `#[serde_zod(schema = \"...\")]`"
+)]
+pub trait ZodSchema {
+    /// The name the schema is exported as, like `User` in `export const User = ...`
+    fn schema_name() -> &'static str;
+    /// The Typescript that exports the schema
+    fn schema_source() -> String;
+    /// `schema_source` as declared in a bundle, referring to the schemas named in `lazy` through
+    /// `z.lazy`, as they're declared after this one, and to every schema without its module path
+    fn schema_source_with_lazy(lazy: &[&str]) -> String;
+    /// The imports `schema_source` relies on, like `import z from "zod";`
+    fn imports() -> String;
+    /// The other schemas that `schema_source` refers to by name
+    fn dependencies() -> Vec<Dependency>;
+}
+
+/// A [`ZodSchema`] that is a plain `z.object()`, implemented by `#[serde_zod::codegen]` for
+/// structs whose schema isn't wrapped in anything, so it can be passed to `.merge()` when the
+/// type is `#[serde(flatten)]`ed
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be flattened, as its schema isn't a plain `z.object()`",
+    note = "only structs without aliases that don't refer to themselves can be merged into another schema"
+)]
+pub trait ZodObjectSchema: ZodSchema {}
+
+/// A [`ZodSchema`] for the keys of a map, which serde_json writes as strings, implemented by
+/// `#[serde_zod::codegen]` for unit enums and newtypes around strings, numbers and bools
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't describe the keys of a map, as serde_json can't write it as a string",
+    note = "only unit enums and newtypes around strings, numbers and bools can be map keys, or describe the field with `#[serde_zod(schema = \"...\")]`"
+)]
+pub trait ZodKeySchema: ZodSchema {
+    /// The schema of the string serde_json writes, like a regex for the digits of a number,
+    /// or `None` when that's the type's own schema
+    fn key_schema_source() -> Option<String>;
+}
+
+/// Another type's schema, as listed by [`ZodSchema::dependencies`]
+#[derive(Debug, Clone, Copy)]
+pub struct Dependency {
+    schema_name: fn() -> &'static str,
+    schema_source: fn() -> String,
+    schema_source_with_lazy: fn(&[&str]) -> String,
+    imports: fn() -> String,
+    dependencies: fn() -> Vec<Dependency>,
+    flattened: bool,
+}
+
+impl Dependency {
+    pub fn of<T: ZodSchema + ?Sized>() -> Self {
+        Self {
+            schema_name: T::schema_name,
+            schema_source: T::schema_source,
+            schema_source_with_lazy: T::schema_source_with_lazy,
+            imports: T::imports,
+            dependencies: T::dependencies,
+            flattened: false,
+        }
+    }
+    /// A dependency through a `#[serde(flatten)]` field, whose schema is passed to `.merge()`,
+    /// so it has to be declared first rather than referred to through `z.lazy`
+    pub fn flattened<T: ZodSchema + ?Sized>() -> Self {
+        Self {
+            flattened: true,
+            ..Self::of::<T>()
+        }
+    }
+    pub fn is_flattened(&self) -> bool {
+        self.flattened
+    }
+    pub fn schema_name(&self) -> &'static str {
+        (self.schema_name)()
+    }
+    pub fn schema_source(&self) -> String {
+        (self.schema_source)()
+    }
+    pub fn schema_source_with_lazy(&self, lazy: &[&str]) -> String {
+        (self.schema_source_with_lazy)(lazy)
+    }
+    pub fn imports(&self) -> String {
+        (self.imports)()
+    }
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        (self.dependencies)()
+    }
+}
+
+/// `source` refers to the schema `name`, which is wrapped in `z.lazy` when it's in `lazy`.
+/// The getter is annotated, as TypeScript can't infer the type of a schema that depends on
+/// its own through another
+#[doc(hidden)]
+pub fn reference(lazy: &[&str], name: &str, source: String) -> String {
+    match lazy.contains(&name) {
+        true => format!("z.lazy((): z.ZodTypeAny => {})", source),
+        false => source,
+    }
+}
+
+enum Visit {
+    InProgress,
+    Done,
+}
+
+/// The imports and the schemas of `roots`, along with every schema they depend on, each
+/// declared before the schemas that use it. When schemas depend on each other, the one
+/// declared first refers to the others through `z.lazy`, except the ones it flattens, which
+/// always come first.
+///
+/// # Panics
+///
+/// When schemas flatten each other, as `.merge()` can't take a schema declared later
+pub fn bundle(roots: &[Dependency]) -> String {
+    let mut seen = HashSet::new();
+    let mut preferred = vec![];
+    for root in roots {
+        visit(*root, &mut seen, &mut preferred);
+    }
+    let mut visits = HashMap::new();
+    let mut ordered = vec![];
+    for dependency in preferred {
+        place(dependency, &mut visits, &mut ordered);
+    }
+    let positions = ordered
+        .iter()
+        .enumerate()
+        .map(|(position, dependency)| (dependency.schema_name(), position))
+        .collect::<HashMap<_, _>>();
+    let mut imports: Vec<String> = vec![];
+    for dependency in &ordered {
+        for line in dependency.imports().lines() {
+            if !imports.iter().any(|import| import == line) {
+                imports.push(line.to_string());
+            }
+        }
+    }
+    let mut sources = vec![imports.join("\n") + "\n"];
+    sources.extend(ordered.iter().enumerate().map(|(position, dependency)| {
+        // a schema refers to itself through `z.lazy` already
+        let lazy = dependency
+            .dependencies()
+            .iter()
+            .map(Dependency::schema_name)
+            .filter(|name| positions[name] > position)
+            .collect::<Vec<_>>();
+        dependency.schema_source_with_lazy(&lazy)
+    }));
+    sources.join("\n")
+}
+
+/// Depth first, so each schema follows its dependencies, except the ones still being
+/// visited further up, which can only come later
+fn visit(dependency: Dependency, seen: &mut HashSet<&'static str>, ordered: &mut Vec<Dependency>) {
+    if !seen.insert(dependency.schema_name()) {
+        return;
+    }
+    for next in dependency.dependencies() {
+        visit(next, seen, ordered);
+    }
+    ordered.push(dependency);
+}
+
+/// In the order `visit` found, but with the schemas each one flattens moved before it
+fn place(
+    dependency: Dependency,
+    visits: &mut HashMap<&'static str, Visit>,
+    ordered: &mut Vec<Dependency>,
+) {
+    let name = dependency.schema_name();
+    match visits.get(name) {
+        Some(Visit::Done) => return,
+        Some(Visit::InProgress) => panic!(
+            "`{}` is flattened into a schema it depends on, which zod's `.merge()` can't describe",
+            name
+        ),
+        None => {}
+    }
+    visits.insert(name, Visit::InProgress);
+    for next in dependency.dependencies() {
+        if next.is_flattened() && next.schema_name() != name {
+            place(next, visits, ordered);
+        }
+    }
+    visits.insert(name, Visit::Done);
+    ordered.push(dependency);
+}
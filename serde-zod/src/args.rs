@@ -0,0 +1,96 @@
+use crate::attrs::Mode;
+use crate::types::object::UnknownKeys;
+use syn::{Error, Ident, Lit, Meta, NestedMeta};
+
+/// The arguments of `#[serde_zod::codegen(...)]`, which configure a single type
+#[derive(Debug, Default)]
+pub struct CodegenArgs {
+    /// `name = "UserDto"`, exported instead of the Rust name
+    pub name: Option<Ident>,
+    /// `strict`, `passthrough` or `strip`, for keys that aren't part of an object
+    pub unknown_keys: Option<UnknownKeys>,
+    /// `type_alias` or `type_alias = false`, whether to also export the TS type of the schema,
+    /// overriding `SERDE_ZOD_TYPE_ALIASES`
+    pub type_alias: Option<bool>,
+    /// `input` or `output`, instead of inferring it from the serde derives
+    pub mode: Option<(Mode, Ident)>,
+    /// `debug`, as if the type was listed in `SERDE_ZOD_DEBUG`
+    pub debug: bool,
+}
+
+impl CodegenArgs {
+    pub fn from_args(args: &[NestedMeta]) -> syn::Result<Self> {
+        let mut codegen = Self::default();
+        let results = args.iter().map(|arg| codegen.apply(arg));
+        crate::error::collect(results.collect::<Vec<_>>())?;
+        Ok(codegen)
+    }
+
+    fn apply(&mut self, arg: &NestedMeta) -> syn::Result<()> {
+        let meta = match arg {
+            NestedMeta::Meta(meta) => meta,
+            NestedMeta::Lit(lit) => return Err(Error::new_spanned(lit, "expected an argument")),
+        };
+        let ident = meta
+            .path()
+            .get_ident()
+            .ok_or_else(|| Error::new_spanned(meta.path(), "unknown argument"))?;
+        match (ident.to_string().as_str(), meta) {
+            ("name", Meta::NameValue(nv)) => match &nv.lit {
+                // the name is exported as a JS identifier
+                Lit::Str(name) => match syn::parse_str::<Ident>(&name.value()) {
+                    Ok(mut parsed) => {
+                        parsed.set_span(name.span());
+                        once(&mut self.name, parsed, ident)
+                    }
+                    Err(_) => Err(Error::new_spanned(name, "expected an identifier")),
+                },
+                lit => Err(Error::new_spanned(lit, "expected a string for `name`")),
+            },
+            ("strict", Meta::Path(_)) => once(&mut self.unknown_keys, UnknownKeys::Strict, ident),
+            ("passthrough", Meta::Path(_)) => {
+                once(&mut self.unknown_keys, UnknownKeys::Passthrough, ident)
+            }
+            ("strip", Meta::Path(_)) => once(&mut self.unknown_keys, UnknownKeys::Strip, ident),
+            ("type_alias", Meta::Path(_)) => once(&mut self.type_alias, true, ident),
+            ("type_alias", Meta::NameValue(nv)) => match &nv.lit {
+                Lit::Bool(value) => once(&mut self.type_alias, value.value, ident),
+                lit => Err(Error::new_spanned(lit, "expected `true` or `false`")),
+            },
+            ("input", Meta::Path(_)) => once(&mut self.mode, (Mode::Input, ident.clone()), ident),
+            ("output", Meta::Path(_)) => once(&mut self.mode, (Mode::Output, ident.clone()), ident),
+            ("debug", Meta::Path(_)) => {
+                self.debug = true;
+                Ok(())
+            }
+            ("name", _) => Err(Error::new_spanned(meta, "expected `name = \"...\"`")),
+            ("type_alias", _) => Err(Error::new_spanned(
+                meta,
+                "expected `type_alias` or `type_alias = false`",
+            )),
+            ("strict" | "passthrough" | "strip" | "input" | "output" | "debug", _) => Err(
+                Error::new_spanned(meta, format!("`{}` doesn't take a value", ident)),
+            ),
+            _ => Err(Error::new_spanned(
+                ident,
+                format!(
+                    "unknown argument `{}`, expected one of `name`, `strict`, `passthrough`, \
+                     `strip`, `type_alias`, `input`, `output`, `debug`",
+                    ident
+                ),
+            )),
+        }
+    }
+}
+
+/// Settings that exclude each other, like `strict` and `passthrough`, can only be given once
+fn once<T>(setting: &mut Option<T>, value: T, ident: &Ident) -> syn::Result<()> {
+    if setting.is_some() {
+        return Err(Error::new_spanned(
+            ident,
+            format!("`{}` conflicts with an earlier argument", ident),
+        ));
+    }
+    *setting = Some(value);
+    Ok(())
+}
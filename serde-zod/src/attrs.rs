@@ -0,0 +1,446 @@
+use crate::case::RenameRule;
+use crate::types::object::UnknownKeys;
+use crate::types::ty::Ty;
+use syn::{Attribute, Error, Lit, Meta, MetaNameValue, NestedMeta};
+
+/// Which side of serde a generated schema describes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// the JSON written by `serde::Serialize`
+    Output,
+    /// the JSON accepted by `serde::Deserialize`
+    Input,
+}
+
+/// A setting that serde allows to differ between serializing and deserializing, for example
+/// `#[serde(rename(serialize = "a", deserialize = "b"))]`
+#[derive(Debug, Clone, Default)]
+pub struct SerDe<T> {
+    pub serialize: T,
+    pub deserialize: T,
+}
+
+impl<T> SerDe<T> {
+    pub fn both(value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            serialize: value.clone(),
+            deserialize: value,
+        }
+    }
+    pub fn get(&self, mode: Mode) -> &T {
+        match mode {
+            Mode::Output => &self.serialize,
+            Mode::Input => &self.deserialize,
+        }
+    }
+}
+
+/// The container-level `#[serde(...)]` attributes that change the shape of the generated schema
+#[derive(Debug)]
+pub struct ContainerAttrs {
+    pub mode: Mode,
+    pub tag: Option<String>,
+    /// with `tag`, the key holding a variant's fields (adjacently tagged)
+    pub content: Option<String>,
+    /// variants are written without any tag
+    pub untagged: bool,
+    /// missing fields are filled in from the struct's `Default`, via `default` or `default = "..."`
+    pub default: bool,
+    /// applied to struct fields, or to the variant names of an enum
+    pub rename_all: SerDe<RenameRule>,
+    /// applied to the fields of every struct variant of an enum
+    pub rename_all_fields: SerDe<RenameRule>,
+    /// `deny_unknown_fields` makes serde reject unknown keys when deserializing
+    pub unknown_keys: UnknownKeys,
+    /// a struct written as its only field
+    pub transparent: bool,
+    /// the name serde gives the type, which it writes as the `tag` of a struct
+    pub name: SerDe<String>,
+}
+
+impl ContainerAttrs {
+    pub fn from_attrs(attrs: &[Attribute], ident: &syn::Ident, mode: Mode) -> syn::Result<Self> {
+        let mut container = Self {
+            mode,
+            tag: None,
+            content: None,
+            untagged: false,
+            default: false,
+            rename_all: Default::default(),
+            rename_all_fields: Default::default(),
+            unknown_keys: Default::default(),
+            transparent: false,
+            name: SerDe::both(unraw(ident)),
+        };
+        let metas = metas(attrs, "serde")?;
+        crate::error::collect(metas.iter().map(|meta| container.apply(meta)))?;
+        Ok(container)
+    }
+
+    fn apply(&mut self, meta: &Meta) -> syn::Result<()> {
+        let mode = self.mode;
+        match (meta_name(meta).as_deref(), meta) {
+            (Some("tag"), Meta::NameValue(nv)) => self.tag = Some(str_value(nv)?),
+            (Some("content"), Meta::NameValue(nv)) => self.content = Some(str_value(nv)?),
+            (Some("untagged"), Meta::Path(_)) => self.untagged = true,
+            (Some("deny_unknown_fields"), Meta::Path(_)) if mode == Mode::Input => {
+                self.unknown_keys = UnknownKeys::Strict
+            }
+            (Some("default"), _) => self.default = true,
+            (Some("rename"), _) => {
+                let names = names(meta)?;
+                if let Some(name) = names.serialize {
+                    self.name.serialize = name;
+                }
+                if let Some(name) = names.deserialize {
+                    self.name.deserialize = name;
+                }
+            }
+            (Some("rename_all"), _) => self.rename_all = rename_rules(meta)?,
+            (Some("rename_all_fields"), _) => self.rename_all_fields = rename_rules(meta)?,
+            (Some("transparent"), Meta::Path(_)) => self.transparent = true,
+            (Some("into"), _) if mode == Mode::Output => return unsupported(meta),
+            (Some("from" | "try_from" | "variant_identifier" | "field_identifier"), _)
+                if mode == Mode::Input =>
+            {
+                return unsupported(meta)
+            }
+            (Some("remote"), _) => return unsupported(meta),
+            // everything else is either validated by serde itself, or doesn't change the JSON
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The `#[serde(...)]` attributes on a single enum variant
+#[derive(Debug, Default)]
+pub struct VariantAttrs {
+    pub rename: SerDe<Option<String>>,
+    pub aliases: Vec<String>,
+    /// from `skip`, `skip_serializing` and `skip_deserializing`
+    pub skip: SerDe<bool>,
+    /// applied to the fields of this variant only
+    pub rename_all: SerDe<RenameRule>,
+}
+
+impl VariantAttrs {
+    pub fn from_attrs(attrs: &[Attribute], mode: Mode) -> syn::Result<Self> {
+        let mut variant = Self::default();
+        let metas = metas(attrs, "serde")?;
+        crate::error::collect(metas.iter().map(|meta| variant.apply(meta, mode)))?;
+        Ok(variant)
+    }
+    fn apply(&mut self, meta: &Meta, mode: Mode) -> syn::Result<()> {
+        match (meta_name(meta).as_deref(), meta) {
+            (Some("rename"), _) => self.rename = names(meta)?,
+            (Some("alias"), Meta::NameValue(nv)) => self.aliases.push(str_value(nv)?),
+            (Some("skip"), Meta::Path(_)) => self.skip = SerDe::both(true),
+            (Some("skip_serializing"), Meta::Path(_)) => self.skip.serialize = true,
+            (Some("skip_deserializing"), Meta::Path(_)) => self.skip.deserialize = true,
+            (Some("rename_all"), _) => self.rename_all = rename_rules(meta)?,
+            (Some("with" | "untagged"), _) => return unsupported(meta),
+            (Some("serialize_with"), _) if mode == Mode::Output => return unsupported(meta),
+            (Some("deserialize_with" | "other"), _) if mode == Mode::Input => {
+                return unsupported(meta)
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    /// The name serde uses for this variant
+    pub fn name(&self, ident: &syn::Ident, rename_all: &SerDe<RenameRule>, mode: Mode) -> String {
+        match self.rename.get(mode) {
+            Some(name) => name.clone(),
+            None => rename_all.get(mode).apply_to_variant(&unraw(ident)),
+        }
+    }
+    /// serde refuses to write (or read) a skipped variant, so it's left out of the schema
+    pub fn skipped(&self, mode: Mode) -> bool {
+        *self.skip.get(mode)
+    }
+    /// Other names serde accepts for this variant, which only applies when deserializing
+    pub fn aliases(&self, mode: Mode) -> Vec<String> {
+        match mode {
+            Mode::Output => vec![],
+            Mode::Input => self.aliases.clone(),
+        }
+    }
+}
+
+/// The `#[serde(...)]` attributes on a single struct or variant field
+#[derive(Debug, Default)]
+pub struct FieldAttrs {
+    pub rename: SerDe<Option<String>>,
+    pub aliases: Vec<String>,
+    /// from `skip`, `skip_serializing` and `skip_deserializing`
+    pub skip: SerDe<bool>,
+    /// the predicate from `skip_serializing_if = "..."`
+    pub skip_serializing_if: Option<String>,
+    /// where the value of a missing field comes from when deserializing
+    pub default: Option<FieldDefault>,
+    pub flatten: bool,
+    /// `#[serde_zod(schema = "...")]`, the zod source describing the field in place of its
+    /// type's own schema, for types from other crates
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDefault {
+    /// `#[serde(default)]`, the field type's `Default`
+    Trait,
+    /// `#[serde(default = "path")]`, a function that can't be evaluated here
+    Path(String),
+    /// `#[serde(default)]` on the struct, taken from the struct's `Default`
+    Container,
+}
+
+impl FieldAttrs {
+    pub fn from_attrs(attrs: &[Attribute], mode: Mode) -> syn::Result<Self> {
+        let mut field = Self::default();
+        let serde = metas(attrs, "serde")?
+            .iter()
+            .map(|meta| field.apply(meta, mode))
+            .collect::<Vec<_>>();
+        let zod = metas(attrs, "serde_zod")?
+            .iter()
+            .map(|meta| field.apply_zod(meta))
+            .collect::<Vec<_>>();
+        crate::error::collect(serde.into_iter().chain(zod))?;
+        Ok(field)
+    }
+    fn apply(&mut self, meta: &Meta, mode: Mode) -> syn::Result<()> {
+        match (meta_name(meta).as_deref(), meta) {
+            (Some("rename"), _) => self.rename = names(meta)?,
+            (Some("alias"), Meta::NameValue(nv)) => self.aliases.push(str_value(nv)?),
+            (Some("skip"), Meta::Path(_)) => self.skip = SerDe::both(true),
+            (Some("skip_serializing"), Meta::Path(_)) => self.skip.serialize = true,
+            (Some("skip_deserializing"), Meta::Path(_)) => self.skip.deserialize = true,
+            (Some("skip_serializing_if"), Meta::NameValue(nv)) => {
+                self.skip_serializing_if = Some(str_value(nv)?)
+            }
+            (Some("default"), Meta::Path(_)) => self.default = Some(FieldDefault::Trait),
+            (Some("flatten"), Meta::Path(_)) => self.flatten = true,
+            (Some("default"), Meta::NameValue(nv)) => {
+                self.default = Some(FieldDefault::Path(str_value(nv)?))
+            }
+            (Some("with"), _) => return unsupported(meta),
+            (Some("serialize_with"), _) if mode == Mode::Output => return unsupported(meta),
+            (Some("deserialize_with"), _) if mode == Mode::Input => return unsupported(meta),
+            _ => {}
+        }
+        Ok(())
+    }
+    fn apply_zod(&mut self, meta: &Meta) -> syn::Result<()> {
+        match (meta_name(meta).as_deref(), meta) {
+            (Some("schema"), Meta::NameValue(nv)) => self.schema = Some(str_value(nv)?),
+            _ => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "expected `#[serde_zod(schema = \"...\")]`",
+                ))
+            }
+        }
+        Ok(())
+    }
+    /// The key serde uses for this field
+    pub fn name(&self, ident: &syn::Ident, rename_all: RenameRule, mode: Mode) -> String {
+        match self.rename.get(mode) {
+            Some(name) => name.clone(),
+            None => rename_all.apply_to_field(&unraw(ident)),
+        }
+    }
+    /// serde never writes a field skipped when serializing, and ignores one skipped
+    /// when deserializing, so either way it's left out of the schema
+    pub fn skipped(&self, mode: Mode) -> bool {
+        *self.skip.get(mode)
+    }
+    /// Whether the key can be absent even though the field isn't skipped entirely
+    pub fn may_be_missing(&self, mode: Mode) -> bool {
+        match mode {
+            Mode::Output => self.skip_serializing_if.is_some(),
+            Mode::Input => self.default.is_some(),
+        }
+    }
+    /// `skip_serializing_if = "Option::is_none"`, so an `Option` field is never `null`
+    pub fn skips_none(&self, mode: Mode) -> bool {
+        mode == Mode::Output
+            && matches!(
+                self.skip_serializing_if.as_deref(),
+                Some("Option::is_none" | "std::option::Option::is_none")
+            )
+    }
+    /// The value serde fills in for a missing key, when it's known without running any Rust
+    pub fn default_value(&self, ty: &Ty, mode: Mode) -> Option<String> {
+        match (mode, &self.default) {
+            (Mode::Input, Some(FieldDefault::Trait)) => ty.default_value(),
+            _ => None,
+        }
+    }
+    /// Other keys serde accepts for this field, which only applies when deserializing
+    pub fn aliases(&self, mode: Mode) -> Vec<String> {
+        match mode {
+            Mode::Output => vec![],
+            Mode::Input => self.aliases.clone(),
+        }
+    }
+}
+
+/// Every item inside the `#[name(...)]` attributes, like `#[serde(...)]`, in order
+fn metas(attrs: &[Attribute], name: &str) -> syn::Result<Vec<Meta>> {
+    let mut metas = vec![];
+    for attr in attrs.iter().filter(|att| att.path.is_ident(name)) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested {
+                if let NestedMeta::Meta(meta) = nested {
+                    metas.push(meta);
+                }
+            }
+        }
+    }
+    Ok(metas)
+}
+
+/// Attributes that hand the JSON over to Rust code, whose output can't be known here
+fn unsupported(meta: &Meta) -> syn::Result<()> {
+    let name = meta_name(meta).unwrap_or_default();
+    Err(Error::new_spanned(
+        meta,
+        format!(
+            "`#[serde({})]` isn't supported, as the JSON it produces can't be described",
+            name
+        ),
+    ))
+}
+
+fn meta_name(meta: &Meta) -> Option<String> {
+    meta.path().get_ident().map(|ident| ident.to_string())
+}
+
+fn str_value(nv: &MetaNameValue) -> syn::Result<String> {
+    match &nv.lit {
+        Lit::Str(str) => Ok(str.value()),
+        lit => Err(Error::new_spanned(
+            lit,
+            format!(
+                "expected a string for `{}`",
+                meta_name(&Meta::NameValue(nv.clone())).unwrap_or_default()
+            ),
+        )),
+    }
+}
+
+/// Either `name = "a"`, or `name(serialize = "a", deserialize = "b")` where both are optional
+fn names(meta: &Meta) -> syn::Result<SerDe<Option<String>>> {
+    match meta {
+        Meta::NameValue(nv) => Ok(SerDe::both(Some(str_value(nv)?))),
+        Meta::List(list) => {
+            let mut names = SerDe::default();
+            for nested in &list.nested {
+                if let NestedMeta::Meta(Meta::NameValue(nv)) = nested {
+                    if nv.path.is_ident("serialize") {
+                        names.serialize = Some(str_value(nv)?);
+                    } else if nv.path.is_ident("deserialize") {
+                        names.deserialize = Some(str_value(nv)?);
+                    }
+                }
+            }
+            Ok(names)
+        }
+        Meta::Path(_) => Ok(SerDe::default()),
+    }
+}
+
+fn rename_rules(meta: &Meta) -> syn::Result<SerDe<RenameRule>> {
+    let names = names(meta)?;
+    let rule = |name: &Option<String>| match name {
+        Some(name) => RenameRule::from_str(name).map_err(|e| Error::new_spanned(meta, e)),
+        None => Ok(RenameRule::None),
+    };
+    Ok(SerDe {
+        serialize: rule(&names.serialize)?,
+        deserialize: rule(&names.deserialize)?,
+    })
+}
+
+/// serde strips the `r#` from raw identifiers like `r#type`
+fn unraw(ident: &syn::Ident) -> String {
+    ident.to_string().trim_start_matches("r#").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn messages(error: Error) -> Vec<String> {
+        error.into_iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unsupported_attrs() {
+        let attrs: Vec<Attribute> = vec![parse_quote!(#[serde(into = "Wire")])];
+        let ident: syn::Ident = parse_quote!(Wrapper);
+        let error = ContainerAttrs::from_attrs(&attrs, &ident, Mode::Output).unwrap_err();
+        assert_eq!(
+            messages(error),
+            ["`#[serde(into)]` isn't supported, as the JSON it produces can't be described"]
+        );
+        // `into` only applies when serializing
+        assert!(ContainerAttrs::from_attrs(&attrs, &ident, Mode::Input).is_ok());
+
+        let attrs: Vec<Attribute> = vec![parse_quote!(#[serde(deserialize_with = "parse")])];
+        assert!(FieldAttrs::from_attrs(&attrs, Mode::Input).is_err());
+        assert!(FieldAttrs::from_attrs(&attrs, Mode::Output).is_ok());
+        let attrs: Vec<Attribute> = vec![parse_quote!(#[serde(with = "module")])];
+        assert!(VariantAttrs::from_attrs(&attrs, Mode::Output).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_attrs_combined() {
+        // every unsupported attribute is reported at once, along with other invalid ones
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[serde(with = "module", rename = 1)]),
+            parse_quote!(#[serde(serialize_with = "write")]),
+        ];
+        let error = FieldAttrs::from_attrs(&attrs, Mode::Output).unwrap_err();
+        assert_eq!(
+            messages(error),
+            [
+                "`#[serde(with)]` isn't supported, as the JSON it produces can't be described",
+                "expected a string for `rename`",
+                "`#[serde(serialize_with)]` isn't supported, as the JSON it produces can't be \
+                 described",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_schema() {
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[serde(rename = "at")]),
+            parse_quote!(#[serde_zod(schema = "z.string().datetime()")]),
+        ];
+        let field = FieldAttrs::from_attrs(&attrs, Mode::Output).unwrap();
+        assert_eq!(field.schema.as_deref(), Some("z.string().datetime()"));
+        assert_eq!(field.rename.serialize.as_deref(), Some("at"));
+        let attrs: Vec<Attribute> = vec![parse_quote!(#[serde_zod(skip)])];
+        let error = FieldAttrs::from_attrs(&attrs, Mode::Output).unwrap_err();
+        assert_eq!(
+            messages(error),
+            ["expected `#[serde_zod(schema = \"...\")]`"]
+        );
+    }
+
+    #[test]
+    fn test_container_name() {
+        let attrs: Vec<Attribute> = vec![parse_quote!(#[serde(rename(serialize = "out"))])];
+        let ident: syn::Ident = parse_quote!(r#Type);
+        let container = ContainerAttrs::from_attrs(&attrs, &ident, Mode::Output).unwrap();
+        assert_eq!(container.name.serialize, "out");
+        assert_eq!(container.name.deserialize, "Type");
+    }
+}
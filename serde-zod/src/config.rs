@@ -0,0 +1,153 @@
+use std::sync::OnceLock;
+
+/// Settings that apply to every type expanded by the macro.
+///
+/// These are read once from the environment of the `cargo` invocation, for example
+/// `SERDE_ZOD_UNIQUE_SETS=1 cargo build`. Since `cargo` doesn't track them, changing
+/// a value requires the crate using the macro to be rebuilt.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// add a `.refine` to set types (`HashSet`, `BTreeSet`) that rejects duplicate items
+    pub unique_sets: bool,
+    /// integers get `.int()` and the bounds of their Rust type
+    pub integers: bool,
+    /// how 64 and 128 bit integers are written, since they don't fit a JS number
+    pub large_integers: LargeIntegers,
+    /// export the TS type of every schema, unless a type opts out with `type_alias = false`
+    pub type_aliases: bool,
+    /// how `Option<T>` is described
+    pub options: Options,
+    /// how types from other modules are referred to
+    pub paths: Paths,
+    /// types whose expansion is reported as a compiler warning, see [`Debug`]
+    pub debug: Debug,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Debug {
+    #[default]
+    Off,
+    /// every type expanded by the macro
+    All,
+    /// only the types with these names, from a comma separated list
+    Types(Vec<String>),
+}
+
+impl Debug {
+    fn from_str(value: &str) -> Self {
+        match value {
+            "" | "0" | "false" | "no" => Self::Off,
+            "1" | "true" | "yes" => Self::All,
+            names => Self::Types(
+                names
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect(),
+            ),
+        }
+    }
+    pub fn enabled(&self, ident: &str) -> bool {
+        match self {
+            Debug::Off => false,
+            Debug::All => true,
+            Debug::Types(names) => names.iter().any(|name| name == ident),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum LargeIntegers {
+    /// `z.number()`, limited to `Number.MAX_SAFE_INTEGER` when checking integers
+    #[default]
+    Number,
+    /// `z.bigint()`, for JSON parsers that produce `bigint`s
+    BigInt,
+    /// `z.string()` of digits, for values serialized as strings
+    String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Options {
+    /// as serde does: `None` is written as `null`, and either `null` or a missing key is read
+    #[default]
+    Serde,
+    /// always `.optional()`, which doesn't accept `null`
+    Optional,
+}
+
+impl Options {
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "serde" => Some(Self::Serde),
+            "optional" => Some(Self::Optional),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Paths {
+    /// `crate::models::User` is `User`
+    #[default]
+    Last,
+    /// `crate::models::User` is `models.User`, for schemas exported from a TS module per
+    /// Rust module and imported with `import * as models from "./models"`
+    Qualified,
+}
+
+impl Paths {
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "last" => Some(Self::Last),
+            "qualified" => Some(Self::Qualified),
+            _ => None,
+        }
+    }
+}
+
+impl LargeIntegers {
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "number" => Some(Self::Number),
+            "bigint" => Some(Self::BigInt),
+            "string" => Some(Self::String),
+            _ => None,
+        }
+    }
+}
+
+impl Config {
+    pub fn global() -> &'static Config {
+        static CONFIG: OnceLock<Config> = OnceLock::new();
+        CONFIG.get_or_init(Config::from_env)
+    }
+    fn from_env() -> Self {
+        Self {
+            unique_sets: env_flag("SERDE_ZOD_UNIQUE_SETS"),
+            integers: env_flag("SERDE_ZOD_INTEGERS"),
+            options: std::env::var("SERDE_ZOD_OPTIONS")
+                .ok()
+                .and_then(|value| Options::from_str(&value))
+                .unwrap_or_default(),
+            paths: std::env::var("SERDE_ZOD_PATHS")
+                .ok()
+                .and_then(|value| Paths::from_str(&value))
+                .unwrap_or_default(),
+            type_aliases: env_flag("SERDE_ZOD_TYPE_ALIASES"),
+            large_integers: std::env::var("SERDE_ZOD_LARGE_INTEGERS")
+                .ok()
+                .and_then(|value| LargeIntegers::from_str(&value))
+                .unwrap_or_default(),
+            debug: std::env::var("SERDE_ZOD_DEBUG")
+                .map(|value| Debug::from_str(&value))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn env_flag(name: &str) -> bool {
+    matches!(
+        std::env::var(name).as_deref(),
+        Ok("1") | Ok("true") | Ok("yes")
+    )
+}
@@ -0,0 +1,19 @@
+use syn::Error;
+
+/// Every value, or all of the errors combined into a single diagnostic, so that each
+/// problem is reported at once rather than one per build
+pub fn collect<T>(results: impl IntoIterator<Item = syn::Result<T>>) -> syn::Result<Vec<T>> {
+    let mut values = vec![];
+    let mut error: Option<Error> = None;
+    for result in results {
+        match (result, &mut error) {
+            (Ok(value), _) => values.push(value),
+            (Err(e), Some(error)) => error.combine(e),
+            (Err(e), None) => error = Some(e),
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(values),
+    }
+}
@@ -7,55 +7,15 @@
 
 use std::borrow::Cow;
 
-/// Indents every line that is not empty by the given number of spaces, starting from the second
-/// line.
-///
-/// The first line of the string is not indented so that it can be placed after an introduction
-/// sequence that has already begun the line.
-///
-/// For the version that also indents the first line, see [indent_all_by].
-pub fn indent_by<'a, S>(number_of_spaces: usize, input: S) -> String
-where
-    S: Into<Cow<'a, str>>,
-{
-    indent(" ".repeat(number_of_spaces), input, false)
-}
-
-/// Indents every line that is not empty with the given prefix, starting from the second line.
-///
-/// The first line of the string is not indented so that it can be placed after an introduction
-/// sequence that has already begun the line.
-/// For the version that also indents the first line, see [indent_all_with].
-pub fn indent_with<'a, S, T>(prefix: S, input: T) -> String
-where
-    S: Into<Cow<'a, str>>,
-    T: Into<Cow<'a, str>>,
-{
-    indent(prefix, input, false)
-}
-
 /// Indents every line that is not empty by the given number of spaces.
-///
-/// For the version that doesn't indent the first line, see [indent_by].
 pub fn indent_all_by<'a, S>(number_of_spaces: usize, input: S) -> String
 where
     S: Into<Cow<'a, str>>,
 {
-    indent(" ".repeat(number_of_spaces), input, true)
-}
-
-/// Indents every line that is not empty with the given prefix.
-///
-/// For the version that also indents the first line, see [indent_with].
-pub fn indent_all_with<'a, S, T>(prefix: S, input: T) -> String
-where
-    S: Into<Cow<'a, str>>,
-    T: Into<Cow<'a, str>>,
-{
-    indent(prefix, input, true)
+    indent(" ".repeat(number_of_spaces), input)
 }
 
-fn indent<'a, S, T>(prefix: S, input: T, indent_all: bool) -> String
+fn indent<'a, S, T>(prefix: S, input: T) -> String
 where
     S: Into<Cow<'a, str>>,
     T: Into<Cow<'a, str>>,
@@ -68,11 +28,8 @@ where
     for (i, line) in input.lines().enumerate() {
         if i > 0 {
             output.push('\n');
-
-            if !line.is_empty() {
-                output.push_str(&prefix);
-            }
-        } else if indent_all && !line.is_empty() {
+        }
+        if !line.is_empty() {
             output.push_str(&prefix);
         }
 
@@ -90,66 +47,6 @@ where
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_single_line_without_newline() {
-        assert_eq!(indent_by(2, "foo"), "foo")
-    }
-
-    #[test]
-    fn test_single_line_with_newline() {
-        assert_eq!(indent_by(2, "foo\n"), "foo\n")
-    }
-
-    #[test]
-    fn test_multiline_without_newline() {
-        assert_eq!(
-            indent_by(
-                2, "
-foo
-bar"
-            ),
-            "
-  foo
-  bar"
-        )
-    }
-
-    #[test]
-    fn test_multiline_with_newline() {
-        assert_eq!(
-            indent_by(
-                2,
-                "
-foo
-bar
-"
-            ),
-            "
-  foo
-  bar
-"
-        )
-    }
-
-    #[test]
-    fn test_empty_line() {
-        assert_eq!(
-            indent_by(
-                2,
-                "
-foo
-
-bar
-"
-            ),
-            "
-  foo
-
-  bar
-"
-        )
-    }
-
     #[test]
     fn test_indent_all_by_empty_line() {
         assert_eq!(
@@ -182,35 +79,7 @@ bar"
     }
 
     #[test]
-    fn test_indent_with() {
-        assert_eq!(
-            indent_with(
-                "  ",
-                "
-foo
-
-bar
-"
-            ),
-            "
-  foo
-
-  bar
-"
-        )
-    }
-
-    #[test]
-    fn test_indent_all_with() {
-        assert_eq!(
-            indent_all_with(
-                "  ", "foo
-
-bar"
-            ),
-            "  foo
-
-  bar"
-        )
+    fn test_indent_all_by_with_newline() {
+        assert_eq!(indent_all_by(2, "foo\n"), "  foo\n")
     }
 }
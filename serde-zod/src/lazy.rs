@@ -0,0 +1,155 @@
+//! References to other schemas are resolved when the source is generated rather than here.
+//! The name a schema is exported as comes from its own `ZodSchema` implementation, which
+//! follows `name = "..."`, and whether a reference goes through `z.lazy` depends on the order
+//! a bundle declares the schemas in. So references are printed between markers, and the
+//! generated code fills in each one
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::str::Chars;
+
+const START: char = '\u{1}';
+const BODY: char = '\u{2}';
+const END: char = '\u{3}';
+
+/// How a reference to another schema may be written
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    /// through `z.lazy` when the schema is declared later
+    Deferrable,
+    /// always the schema itself, like one passed to `.merge()`
+    Eager,
+    /// the keys of a map, as the string serde_json writes for them, see `ZodKeySchema`
+    Key,
+}
+
+/// A reference to the schema of the type's `index`th dependency, written after `prefix` (the
+/// module path, with `SERDE_ZOD_PATHS=qualified`) and followed by `rest`, like the arguments
+/// of a generic schema
+pub fn mark(index: usize, prefix: &str, kind: Kind, rest: &str) -> String {
+    let flag = match kind {
+        Kind::Deferrable => 'l',
+        Kind::Eager => 'n',
+        Kind::Key => 'k',
+    };
+    format!(
+        "{}{}{}{}{}{}{}{}",
+        START, flag, index, BODY, prefix, BODY, rest, END
+    )
+}
+
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Reference {
+        index: usize,
+        prefix: String,
+        kind: Kind,
+        parts: Vec<Part>,
+    },
+}
+
+fn parse(chars: &mut Chars) -> Vec<Part> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            START => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                let kind = match chars.next() {
+                    Some('l') => Kind::Deferrable,
+                    Some('k') => Kind::Key,
+                    _ => Kind::Eager,
+                };
+                let index: String = chars.by_ref().take_while(|c| *c != BODY).collect();
+                let prefix = chars.by_ref().take_while(|c| *c != BODY).collect();
+                parts.push(Part::Reference {
+                    index: index.parse().unwrap_or_default(),
+                    prefix,
+                    kind,
+                    parts: parse(chars),
+                });
+            }
+            END => break,
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    parts
+}
+
+/// With the names the referenced types have in Rust, as the best guess available here
+fn plain(parts: &[Part], names: &[String]) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            Part::Text(text) => text.clone(),
+            Part::Reference {
+                index,
+                prefix,
+                parts,
+                ..
+            } => format!("{}{}{}", prefix, names[*index], plain(parts, names)),
+        })
+        .collect()
+}
+
+fn expr(parts: &[Part]) -> TokenStream {
+    let parts = parts.iter().map(|part| match part {
+        Part::Text(text) => quote!(::std::string::String::from(#text)),
+        Part::Reference {
+            index,
+            prefix,
+            kind,
+            parts,
+        } => {
+            let rest = expr(parts);
+            // a bundle declares every schema in the one module
+            let prefix = match prefix.is_empty() {
+                true => quote!(""),
+                false => quote!(if qualified { #prefix } else { "" }),
+            };
+            let source = quote!(::std::format!("{}{}{}", #prefix, names[#index], #rest));
+            match kind {
+                Kind::Deferrable => {
+                    quote!(::serde_zod_runtime::reference(lazy, names[#index], #source))
+                }
+                Kind::Eager => source,
+                Kind::Key => quote!(match &keys[#index] {
+                    ::std::option::Option::Some(key) => key.clone(),
+                    ::std::option::Option::None => #source,
+                }),
+            }
+        }
+    });
+    let parts = parts.collect::<Vec<_>>();
+    if parts.is_empty() {
+        return quote!(::std::string::String::new());
+    }
+    quote!([#(#parts),*].concat::<str>())
+}
+
+/// The source with the referenced schemas as they're named in Rust, for debugging, and an
+/// expression that builds it from the exported `names: [&str; N]` of the dependencies, with
+/// those in `lazy: &[&str]` referred to through `z.lazy`, and map keys replaced by the
+/// `keys: [Option<String>; N]` that serde_json writes in their place. Module paths are only
+/// written when `qualified: bool` is set
+pub fn split(marked: &str, names: &[String]) -> (String, TokenStream) {
+    let parts = parse(&mut marked.chars());
+    (plain(&parts, names), expr(&parts))
+}
+
+#[test]
+fn test_split_nested() {
+    let user = mark(1, "", Kind::Deferrable, "");
+    let page = mark(0, "", Kind::Deferrable, &format!("({})", user));
+    let names = ["Page".to_string(), "User".to_string()];
+    let (source, _) = split(&format!("z.object({{ page: {} }})", page), &names);
+    assert_eq!(source, "z.object({ page: Page(User) })");
+    let (source, _) = split(&mark(1, "models.", Kind::Eager, ""), &names);
+    assert_eq!(source, "models.User");
+}
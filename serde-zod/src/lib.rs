@@ -1,4 +1,10 @@
+mod args;
+mod attrs;
+mod case;
+mod config;
+mod error;
 mod indent;
+mod lazy;
 mod printer;
 mod types;
 mod zod;
@@ -6,37 +12,49 @@ mod zod;
 extern crate proc_macro;
 // use indenter;
 
+use crate::args::CodegenArgs;
+use crate::attrs::{ContainerAttrs, FieldAttrs, FieldDefault, Mode, VariantAttrs};
+use crate::case::RenameRule;
+use crate::config::{Config, Options, Paths};
 use crate::printer::Print;
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
-use quote::quote;
-use std::collections::HashMap;
+use quote::{quote, ToTokens};
 use std::convert::TryFrom;
 
 use zod::*;
 
+use crate::named_ty::NamedTy;
 use crate::tagged_union::TaggedUnion;
+use crate::types::int::IntKind;
 use crate::types::zod_enum::{Enum, EnumUnitVariant};
-use crate::union::UnionVariant;
+use crate::union::{UnionVariant, UnionVariantFields};
+use crate::untagged_union::UntaggedUnion;
 use crate::zod::Program;
 
 use syn::{
-    parse_macro_input, Attribute, Data, DataEnum, DataStruct, DeriveInput, Error, Fields,
-    GenericArgument, Meta, MetaNameValue, NestedMeta, PathArguments, Type,
+    parse_macro_input, Attribute, AttributeArgs, Data, DataEnum, DataStruct, DeriveInput, Error,
+    Expr, ExprLit, Fields, FieldsUnnamed, GenericArgument, Lit, Meta, NestedMeta, PathArguments,
+    Type,
 };
 use types::ty::Ty;
-use types::{import, object, tagged_union, union};
+use types::{import, named_ty, object, tagged_union, union, untagged_union};
 
 /// Example of user-defined [procedural macro attribute][1].
 ///
 /// [1]: https://doc.rust-lang.org/reference/procedural-macros.html#attribute-macros
 #[proc_macro_attribute]
-pub fn codegen(_attr: TokenStream, input: TokenStream) -> TokenStream {
+pub fn codegen(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as AttributeArgs);
     let input_parsed = parse_macro_input!(input as DeriveInput);
-    let serde_derive = has_serde_derive(&input_parsed.attrs);
-    let serde_attrs = serde_attrs(&input_parsed.attrs);
+    let serde_derives = serde_derives(&input_parsed.attrs);
 
-    if !serde_derive {
+    let args = match CodegenArgs::from_args(&args) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    if serde_derives.is_empty() {
         return Error::new(
             Span::call_site(),
             "must derive serde::Serialize or serde::Deserialize",
@@ -45,34 +63,87 @@ pub fn codegen(_attr: TokenStream, input: TokenStream) -> TokenStream {
         .into();
     }
 
+    // a type that is only ever deserialized is described by what serde accepts
+    let mode = match &args.mode {
+        Some((mode, arg)) => {
+            let derive = match mode {
+                Mode::Output => "Serialize",
+                Mode::Input => "Deserialize",
+            };
+            if !serde_derives.iter().any(|d| d == derive) {
+                let message = format!("`{}` requires deriving serde::{}", arg, derive);
+                return Error::new_spanned(arg, message).to_compile_error().into();
+            }
+            *mode
+        }
+        None if serde_derives.iter().all(|derive| derive == "Deserialize") => Mode::Input,
+        None => Mode::Output,
+    };
+
+    let mut container_attrs =
+        match ContainerAttrs::from_attrs(&input_parsed.attrs, &input_parsed.ident, mode) {
+            Ok(container_attrs) => container_attrs,
+            Err(e) => return e.to_compile_error().into(),
+        };
+    if let Some(unknown_keys) = args.unknown_keys {
+        container_attrs.unknown_keys = unknown_keys;
+    }
+
     let impl_ident = input_parsed.ident.clone();
+    let ident = args.name.as_ref().unwrap_or(&input_parsed.ident);
 
-    let statements: Result<StatementList, _> = match &input_parsed.data {
-        Data::Struct(st) => StatementList::try_from((&input_parsed.ident, st)),
-        Data::Union(_) => todo!("Data::Union"),
+    let statements: syn::Result<StatementList> = match &input_parsed.data {
+        Data::Struct(st) => StatementList::try_from((ident, st, &container_attrs)),
+        Data::Union(u) => Err(Error::new_spanned(
+            u.union_token,
+            "unions can't be described, as serde doesn't support them",
+        )),
         Data::Enum(e) => {
-            let tag = serde_attrs.get("tag");
-            if let Some(tag) = tag {
-                StatementList::try_from((EnumKind::Tagged(tag.clone()), &input_parsed.ident, e))
+            // skipped variants are never written (or read) by serde, so they play no
+            // part in deciding how the enum is represented
+            let skipped =
+                match error::collect(e.variants.iter().map(|v| {
+                    VariantAttrs::from_attrs(&v.attrs, mode).map(|attrs| attrs.skipped(mode))
+                })) {
+                    Ok(skipped) => skipped,
+                    Err(e) => return e.to_compile_error().into(),
+                };
+            let mut e = e.clone();
+            e.variants = e
+                .variants
+                .into_iter()
+                .zip(skipped)
+                .filter(|(_, skipped)| !skipped)
+                .map(|(v, _)| v)
+                .collect();
+            let e = &e;
+            if e.variants.is_empty() {
+                StatementList::try_from((EnumKind::Empty, ident, e, &container_attrs))
+            } else if container_attrs.untagged {
+                StatementList::try_from((EnumKind::Untagged, ident, e, &container_attrs))
+            } else if let Some(tag) = &container_attrs.tag {
+                let kind = match &container_attrs.content {
+                    Some(content) => EnumKind::AdjacentlyTagged {
+                        tag: tag.clone(),
+                        content: content.clone(),
+                    },
+                    None => EnumKind::Tagged(tag.clone()),
+                };
+                StatementList::try_from((kind, ident, e, &container_attrs))
             } else {
                 let all_unit = e.variants.iter().all(|v| matches!(&v.fields, Fields::Unit));
                 if all_unit {
-                    StatementList::try_from((EnumKind::UnitOnly, &input_parsed.ident, e))
+                    StatementList::try_from((EnumKind::UnitOnly, ident, e, &container_attrs))
                 } else {
-                    StatementList::try_from((EnumKind::Mixed, &input_parsed.ident, e))
+                    StatementList::try_from((EnumKind::Mixed, ident, e, &container_attrs))
                 }
             }
         }
     };
 
-    let statements = match statements {
+    let mut statements = match statements {
         Ok(statements) => statements,
-        Err(e) => {
-            eprintln!("{}", e);
-            return Error::new(Span::call_site(), "Couldn't create statements")
-                .to_compile_error()
-                .into();
-        }
+        Err(e) => return e.to_compile_error().into(),
     };
 
     let mut p = Program {
@@ -85,99 +156,485 @@ pub fn codegen(_attr: TokenStream, input: TokenStream) -> TokenStream {
         path: "zod".into(),
     });
 
-    p.statements.extend(statements.0);
+    let params = input_parsed
+        .generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect::<Vec<_>>();
 
-    let mut st = String::new();
+    let name = ident.to_string();
+    let rust_name = input_parsed.ident.to_string();
+    let mut recursive = false;
+    for statement in &mut statements.0 {
+        if let Statement::Export(item) = statement {
+            // `Self` refers to the type being described, by its exported name, and a generic
+            // one to the schema for its own parameters, like `Tree(T)`
+            item.walk_mut(&mut |ty| {
+                if let Ty::Reference { ident, args, .. } = ty {
+                    if ident == "Self" {
+                        *ident = name.clone();
+                        *args = params
+                            .iter()
+                            .map(|param| Ty::Reference {
+                                ident: param.clone(),
+                                args: vec![],
+                                rust: Some(
+                                    Ident::new(param, Span::call_site()).into_token_stream(),
+                                ),
+                            })
+                            .collect();
+                    }
+                }
+            });
+            // a reference back to the schema being defined must wait until it's initialised,
+            // and uses the exported name when the type is referred to by its Rust one. Only an
+            // unqualified path is known to be this type, as `a::Node` may be another `Node`
+            item.walk_mut(&mut |ty| match ty {
+                Ty::Reference {
+                    ident,
+                    rust: Some(rust),
+                    ..
+                } if (*ident == name || *ident == rust_name) && unqualified(rust) => {
+                    *ident = name.clone();
+                    recursive = true;
+                    *ty = Ty::Lazy {
+                        ty: Box::new(ty.clone()),
+                        any: !params.is_empty(),
+                    }
+                }
+                _ => {}
+            });
+        }
+    }
+    // the schemas merged into this one must be plain objects, which includes type parameters
+    // and this type itself, neither of which are known to be
+    let mut merged = vec![];
+    for statement in &mut statements.0 {
+        if let Statement::Export(item) = statement {
+            item.walk_fields_mut(&mut |ty, flatten| {
+                if let Some(rust) = merged_rust(ty).filter(|_| flatten) {
+                    merged.push(rust.clone());
+                }
+            });
+        }
+    }
+    // a plain object can be merged in turn, unless aliases wrap it in `z.preprocess`
+    let object = statements.0.iter().any(|statement| match statement {
+        Statement::Export(Item::Object(object)) => {
+            object.fields.iter().all(|field| field.aliases.is_empty())
+        }
+        _ => false,
+    });
+    let key_form = key_form(&input_parsed.data, &statements.0, &container_attrs);
+    // a generic schema is a function, whose return type is inferred instead
+    if recursive && params.is_empty() {
+        statements.0 = statements
+            .0
+            .into_iter()
+            .map(|statement| match statement {
+                Statement::Export(item) => Statement::ExportRecursive(item),
+                statement => statement,
+            })
+            .collect();
+    }
+    // a recursive schema already exports its type
+    let type_alias = args.type_alias.unwrap_or(Config::global().type_aliases);
+    if type_alias && !(recursive && params.is_empty()) {
+        let input_differs = statements.0.iter().any(|statement| match statement {
+            Statement::Export(item) => item.input_differs(),
+            _ => false,
+        });
+        statements.0.push(Statement::TypeAlias {
+            ident: ident.to_string(),
+            params: params.clone(),
+            input_differs,
+        });
+    }
+    if params.is_empty() {
+        p.statements.extend(statements.0);
+    } else {
+        p.statements
+            .extend(statements.0.into_iter().map(|statement| match statement {
+                Statement::Export(item) => Statement::ExportGeneric {
+                    params: params.clone(),
+                    item,
+                },
+                generic => generic,
+            }));
+    }
+
+    // other schemas are referred to by the names they give themselves, and may have to be
+    // declared after this one in a bundle. `.merge()` needs a flattened schema itself rather
+    // than `z.lazy`, so those have to be declared first. A map key is described by what
+    // serde_json writes for it, which only the key's type knows
+    let dependencies = dependencies(&p.statements, &params);
+    let mut flattened = vec![false; dependencies.len()];
+    let mut keyed = vec![false; dependencies.len()];
+    for statement in &mut p.statements {
+        if let Statement::Export(item)
+        | Statement::ExportRecursive(item)
+        | Statement::ExportGeneric { item, .. } = statement
+        {
+            item.walk_fields_mut(&mut |ty, flatten| {
+                ty.walk_mut(&mut |ty| match ty {
+                    Ty::Reference { ident, args, .. }
+                        if args.is_empty() && params.contains(ident) => {}
+                    Ty::Reference {
+                        ident,
+                        rust: Some(rust),
+                        ..
+                    } if !(*ident == name && unqualified(rust)) => {
+                        let rust = rust.to_string();
+                        if let Some(index) =
+                            dependencies.iter().position(|(_, r)| r.to_string() == rust)
+                        {
+                            let prefix = match ident.rfind('.') {
+                                Some(end) => ident[..=end].to_string(),
+                                None => String::new(),
+                            };
+                            flattened[index] |= flatten;
+                            *ty = Ty::Dependency {
+                                index,
+                                prefix,
+                                kind: match flatten {
+                                    true => lazy::Kind::Eager,
+                                    false => lazy::Kind::Deferrable,
+                                },
+                                ty: Box::new(ty.clone()),
+                            }
+                        }
+                    }
+                    Ty::Record { key, .. } => {
+                        if let Ty::Dependency { index, kind, .. } = key.as_mut() {
+                            *kind = lazy::Kind::Key;
+                            keyed[*index] = true;
+                        }
+                    }
+                    _ => {}
+                })
+            });
+        }
+    }
+    let names = dependencies
+        .iter()
+        .map(|(ident, _)| ident.clone())
+        .collect::<Vec<_>>();
+    let constructors = dependencies
+        .iter()
+        .zip(flattened)
+        .map(|((_, rust), flattened)| match flattened {
+            true => quote!(::serde_zod_runtime::Dependency::flattened::<#rust>()),
+            false => quote!(::serde_zod_runtime::Dependency::of::<#rust>()),
+        })
+        .collect::<Vec<_>>();
+    let keys = dependencies
+        .iter()
+        .zip(keyed)
+        .map(|((_, rust), keyed)| match keyed {
+            true => quote::quote_spanned! {syn::spanned::Spanned::span(rust)=>
+                <#rust as ::serde_zod_runtime::ZodKeySchema>::key_schema_source()
+            },
+            false => quote!(::std::option::Option::None),
+        })
+        .collect::<Vec<_>>();
+    let dependencies = dependencies
+        .into_iter()
+        .map(|(_, rust)| rust)
+        .collect::<Vec<_>>();
+
+    let mut marked = String::new();
     let mut im = String::new();
 
-    p.statements.print(&mut st).expect("printing statements");
-    p.imports.print(&mut im).expect("printing imports");
+    if p.statements.print(&mut marked).is_err() || p.imports.print(&mut im).is_err() {
+        return Error::new(Span::call_site(), "couldn't print the zod schema")
+            .to_compile_error()
+            .into();
+    }
+    let (st, lazy_st) = lazy::split(&marked, &names);
+    let count = dependencies.len();
+
+    let debug = if args.debug || Config::global().debug.enabled(&ident.to_string()) {
+        debug_note(ident, &p, &st)
+    } else {
+        quote! {}
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input_parsed.generics.split_for_impl();
+    let item = without_zod_attrs(input_parsed.clone());
+
+    let key_impl = match key_form {
+        Some(form) => {
+            let form = match form {
+                Some(source) => quote!(::std::option::Option::Some(String::from(#source))),
+                None => quote!(::std::option::Option::None),
+            };
+            quote! {
+                impl #impl_generics ::serde_zod_runtime::ZodKeySchema for #impl_ident #ty_generics #where_clause {
+                    fn key_schema_source() -> Option<String> {
+                        #form
+                    }
+                }
+            }
+        }
+        None => quote! {},
+    };
+    // a recursive schema is annotated as `z.ZodType`, which has no `.merge()`
+    let object_impl = match object && !(recursive && params.is_empty()) {
+        true => quote! {
+            impl #impl_generics ::serde_zod_runtime::ZodObjectSchema for #impl_ident #ty_generics #where_clause {}
+        },
+        false => quote! {},
+    };
+    let merged = merged.iter().map(|rust| {
+        quote::quote_spanned! {syn::spanned::Spanned::span(rust)=>
+            merged::<#rust>();
+        }
+    });
+    let merged_check = quote! {
+        const _: () = {
+            fn merged<T: ::serde_zod_runtime::ZodObjectSchema + ?Sized>() {}
+            #[allow(dead_code)]
+            fn flattened #impl_generics () #where_clause {
+                #(#merged)*
+            }
+        };
+    };
 
+    let source = quote! {
+        let names: [&str; #count] = [
+            #(<#dependencies as ::serde_zod_runtime::ZodSchema>::schema_name()),*
+        ];
+        let keys: [::std::option::Option<String>; #count] = [#(#keys),*];
+        #lazy_st
+    };
     let tokens = quote! {
-        #input_parsed
-        impl #impl_ident {
-            pub fn codegen() -> String {
-                String::from(#st)
+        #item
+        impl #impl_generics ::serde_zod_runtime::ZodSchema for #impl_ident #ty_generics #where_clause {
+            fn schema_name() -> &'static str {
+                #name
+            }
+            #[allow(unused_variables)]
+            fn schema_source() -> String {
+                let lazy: &[&str] = &[];
+                let qualified = true;
+                #source
+            }
+            #[allow(unused_variables)]
+            fn schema_source_with_lazy(lazy: &[&str]) -> String {
+                let qualified = false;
+                #source
             }
-            pub fn print_imports() -> String {
+            fn imports() -> String {
                 String::from(#im)
             }
+            fn dependencies() -> Vec<::serde_zod_runtime::Dependency> {
+                vec![#(#constructors),*]
+            }
         }
+        #object_impl
+        #key_impl
+        #merged_check
+        #debug
     };
 
     tokens.into()
 }
 
+/// The Rust types of the other schemas referred to by name, except for type parameters,
+/// whose schemas are passed in as arguments, along with the name they're expected to have
+fn dependencies(
+    statements: &[Statement],
+    params: &[String],
+) -> Vec<(String, proc_macro2::TokenStream)> {
+    let mut seen = std::collections::HashSet::new();
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Export(item)
+            | Statement::ExportRecursive(item)
+            | Statement::ExportGeneric { item, .. } => Some(item),
+            Statement::TypeAlias { .. } => None,
+        })
+        .flat_map(Item::references)
+        .filter_map(|ty| match ty {
+            Ty::Reference { ident, args, .. } if args.is_empty() && params.contains(ident) => None,
+            Ty::Reference {
+                ident,
+                rust: Some(rust),
+                ..
+            } => Some((
+                ident.rsplit('.').next().unwrap_or(ident).to_string(),
+                rust.clone(),
+            )),
+            _ => None,
+        })
+        .filter(|(_, rust)| seen.insert(rust.to_string()))
+        .collect()
+}
+
+/// `#[serde_zod(...)]` is only read by this macro, so it's removed before the compiler sees it
+fn without_zod_attrs(mut input: DeriveInput) -> DeriveInput {
+    let fields: Vec<&mut Fields> = match &mut input.data {
+        Data::Struct(st) => vec![&mut st.fields],
+        Data::Enum(e) => e.variants.iter_mut().map(|v| &mut v.fields).collect(),
+        Data::Union(_) => vec![],
+    };
+    for field in fields.into_iter().flat_map(|fields| fields.iter_mut()) {
+        field.attrs.retain(|attr| !attr.path.is_ident("serde_zod"));
+    }
+    input
+}
+
+/// Proc macros can't emit notes on stable, so the output is attached to the use of a
+/// deprecated item, which the compiler reports as a warning pointing at the type
+fn debug_note(ident: &Ident, program: &Program, ts: &str) -> proc_macro2::TokenStream {
+    let note = format!(
+        "serde_zod output for `{}`\n\n{:#?}\n\n{}",
+        ident, program, ts
+    );
+    quote::quote_spanned! {ident.span()=>
+        const _: () = {
+            #[deprecated(note = #note)]
+            struct SerdeZodDebug;
+            let _ = SerdeZodDebug;
+        };
+    }
+}
+
 fn process_struct(
     ident: &Ident,
     data_struct: &DataStruct,
-) -> Result<Vec<Statement>, std::fmt::Error> {
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<Statement>> {
+    let bare_ty = match &data_struct.fields {
+        Fields::Unnamed(unnamed) => Some(unnamed_fields_ty(unnamed, attrs.mode)?),
+        // serde writes unit structs as `null`
+        Fields::Unit => Some(Ty::ZodNull),
+        Fields::Named(_) => None,
+    };
+    if let Some(ty) = bare_ty {
+        let named = NamedTy {
+            ident: ident.to_string(),
+            ty,
+        };
+        return Ok(vec![Statement::Export(Item::Ty(named))]);
+    }
+    // serde writes a transparent struct as its one field that isn't skipped
+    if attrs.transparent {
+        let fields = error::collect(
+            data_struct
+                .fields
+                .iter()
+                .map(|field| Ok((field, FieldAttrs::from_attrs(&field.attrs, attrs.mode)?))),
+        )?;
+        let (field, field_attrs) = fields
+            .into_iter()
+            .find(|(_, field_attrs)| !field_attrs.skipped(attrs.mode))
+            .ok_or_else(|| Error::new_spanned(&data_struct.fields, "expected a field"))?;
+        let named = NamedTy {
+            ident: ident.to_string(),
+            ty: field_ty(field, &field_attrs)?,
+        };
+        return Ok(vec![Statement::Export(Item::Ty(named))]);
+    }
     let mut ob = object::Object {
         ident: ident.to_string(),
         fields: Default::default(),
+        unknown_keys: attrs.unknown_keys,
     };
-    for field in &data_struct.fields {
-        let ty = as_ty(&field.ty).expect("ty");
-        if let Some(ident) = &field.ident {
-            ob.fields.push(zod::Field {
-                ident: ident.to_string(),
-                ty,
-            })
+    let rename_all = *attrs.rename_all.get(attrs.mode);
+    let fields = error::collect(data_struct.fields.iter().map(|field| {
+        let mut field_attrs = FieldAttrs::from_attrs(&field.attrs, attrs.mode)?;
+        if field_attrs.skipped(attrs.mode) {
+            return Ok(None);
+        }
+        if attrs.default && field_attrs.default.is_none() {
+            field_attrs.default = Some(FieldDefault::Container);
         }
+        let ty = field_ty(field, &field_attrs)?;
+        Ok(field
+            .ident
+            .as_ref()
+            .map(|ident| zod::Field::from_attrs(ident, ty, &field_attrs, rename_all, attrs.mode)))
+    }))?;
+    // a tagged struct is written with its name under the tag, which serde ignores when reading
+    if let (Some(tag), Mode::Output) = (&attrs.tag, attrs.mode) {
+        let name = attrs.name.get(attrs.mode);
+        ob.fields
+            .push(zod::Field::new(tag, Ty::Literal(name.clone())));
     }
+    ob.fields.extend(fields.into_iter().flatten());
     let statements = vec![Statement::Export(Item::Object(ob))];
     Ok(statements)
 }
 
-fn process_mixed_enum(ident: &Ident, e: &DataEnum) -> Result<Vec<Statement>, std::fmt::Error> {
+fn process_mixed_enum(
+    ident: &Ident,
+    e: &DataEnum,
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<Statement>> {
     let mut zod_union = union::Union {
         ident: ident.to_string(),
         variants: vec![],
+        unknown_keys: attrs.unknown_keys,
     };
-    let variants = extract_variants(e);
+    let variants = extract_variants(e, attrs)?;
     zod_union.variants.extend(variants);
     Ok(vec![Statement::Export(Item::Union(zod_union))])
 }
 
 enum EnumKind {
     Tagged(String),
+    AdjacentlyTagged { tag: String, content: String },
+    Untagged,
+    Empty,
     UnitOnly,
     Mixed,
 }
 
-impl TryFrom<(EnumKind, &Ident, &DataEnum)> for StatementList {
-    type Error = std::fmt::Error;
+impl TryFrom<(EnumKind, &Ident, &DataEnum, &ContainerAttrs)> for StatementList {
+    type Error = Error;
 
-    fn try_from((kind, ident, e_enum): (EnumKind, &Ident, &DataEnum)) -> Result<Self, Self::Error> {
+    fn try_from(
+        (kind, ident, e_enum, attrs): (EnumKind, &Ident, &DataEnum, &ContainerAttrs),
+    ) -> Result<Self, Self::Error> {
         match kind {
-            EnumKind::Tagged(tag) => process_tagged_enum(ident, e_enum, &tag),
-            EnumKind::UnitOnly => process_unit_only_enum(ident, e_enum),
-            EnumKind::Mixed => process_mixed_enum(ident, e_enum),
+            EnumKind::Tagged(tag) => process_tagged_enum(ident, e_enum, &tag, attrs),
+            EnumKind::AdjacentlyTagged { tag, content } => {
+                process_adjacently_tagged_enum(ident, e_enum, &tag, &content, attrs)
+            }
+            EnumKind::Untagged => process_untagged_enum(ident, e_enum, attrs),
+            EnumKind::Empty => Ok(process_empty_enum(ident)),
+            EnumKind::UnitOnly => process_unit_only_enum(ident, e_enum, attrs),
+            EnumKind::Mixed => process_mixed_enum(ident, e_enum, attrs),
         }
         .map(StatementList)
     }
 }
 
-impl TryFrom<(&Ident, &DataStruct)> for StatementList {
-    type Error = std::fmt::Error;
+impl TryFrom<(&Ident, &DataStruct, &ContainerAttrs)> for StatementList {
+    type Error = Error;
 
-    fn try_from((ident, data_struct): (&Ident, &DataStruct)) -> Result<Self, Self::Error> {
-        process_struct(ident, data_struct).map(StatementList)
+    fn try_from(
+        (ident, data_struct, attrs): (&Ident, &DataStruct, &ContainerAttrs),
+    ) -> Result<Self, Self::Error> {
+        process_struct(ident, data_struct, attrs).map(StatementList)
     }
 }
 
-fn process_unit_only_enum(ident: &Ident, e: &DataEnum) -> Result<Vec<Statement>, std::fmt::Error> {
+fn process_unit_only_enum(
+    ident: &Ident,
+    e: &DataEnum,
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<Statement>> {
     let mut zod_enum = Enum::new(ident.to_string());
-    let variants = e
-        .variants
-        .iter()
-        .filter_map(|variant| match variant.fields {
-            Fields::Unit => Some(EnumUnitVariant {
-                ident: variant.ident.to_string(),
-            }),
-            _ => None,
+    let variants = error::collect(e.variants.iter().map(|variant| {
+        let variant_attrs = VariantAttrs::from_attrs(&variant.attrs, attrs.mode)?;
+        Ok(EnumUnitVariant {
+            ident: variant_attrs.name(&variant.ident, &attrs.rename_all, attrs.mode),
+            aliases: variant_attrs.aliases(attrs.mode),
         })
-        .collect();
+    }))?;
     zod_enum.add_variants(variants);
     let statements = vec![Statement::Export(Item::Enum(zod_enum))];
     Ok(statements)
@@ -187,77 +644,386 @@ fn process_tagged_enum(
     ident: &Ident,
     e: &DataEnum,
     tag: &str,
-) -> Result<Vec<Statement>, std::fmt::Error> {
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<Statement>> {
     let mut tagged_union = TaggedUnion::new(ident.to_string(), tag);
-    tagged_union.add_variants(extract_variants(e));
+    tagged_union.unknown_keys = attrs.unknown_keys;
+    let variants = extract_variants(e, attrs)?;
+    // serde writes the fields of a newtype variant's struct or map beside the tag, as if
+    // they were flattened, and a unit struct as the tag alone
+    let variants = error::collect(e.variants.iter().zip(variants).map(|(vari, mut variant)| {
+        if let (Fields::Unnamed(unnamed), UnionVariantFields::Unnamed(ty)) =
+            (&vari.fields, &variant.fields)
+        {
+            let field = unnamed
+                .unnamed
+                .first()
+                .filter(|_| unnamed.unnamed.len() == 1);
+            variant.fields = match (field, ty) {
+                (_, Ty::ZodNull) => UnionVariantFields::Unit,
+                (Some(field), ty) => {
+                    flattenable(ty, &field.ty).map_err(|_| {
+                        Error::new_spanned(
+                            &field.ty,
+                            "an internally tagged variant can only hold a struct or map, \
+                             whose fields serde writes beside the tag",
+                        )
+                    })?;
+                    let mut field = zod::Field::new("", ty.clone());
+                    field.flatten = true;
+                    UnionVariantFields::Named(vec![field])
+                }
+                // serde rejects tuple variants in an internally tagged enum itself
+                (None, _) => return Ok(variant),
+            };
+        }
+        Ok(variant)
+    }))?;
+    tagged_union.add_variants(variants);
     let statements = vec![Statement::Export(Item::TaggedUnion(tagged_union))];
     Ok(statements)
 }
 
-fn extract_variants(data_enum: &DataEnum) -> Vec<UnionVariant> {
-    data_enum
-        .variants
-        .iter()
-        .filter_map(|vari| {
-            let ident = vari.ident.to_string();
-            match &vari.fields {
-                Fields::Named(fields_named) => {
-                    UnionVariant::from_syn_fields_named(ident, fields_named)
-                }
-                Fields::Unnamed(fields) => UnionVariant::from_syn_fields_unnamed(ident, fields),
-                Fields::Unit => Some(UnionVariant::from_unit(ident)),
+fn process_adjacently_tagged_enum(
+    ident: &Ident,
+    e: &DataEnum,
+    tag: &str,
+    content: &str,
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<Statement>> {
+    let mut tagged_union = TaggedUnion::adjacent(ident.to_string(), tag, content);
+    tagged_union.unknown_keys = attrs.unknown_keys;
+    tagged_union.add_variants(extract_variants(e, attrs)?);
+    let statements = vec![Statement::Export(Item::TaggedUnion(tagged_union))];
+    Ok(statements)
+}
+
+/// An enum without variants can't be constructed, so no value is ever valid
+fn process_empty_enum(ident: &Ident) -> Vec<Statement> {
+    let named = NamedTy {
+        ident: ident.to_string(),
+        ty: Ty::ZodNever,
+    };
+    vec![Statement::Export(Item::Ty(named))]
+}
+
+fn process_untagged_enum(
+    ident: &Ident,
+    e: &DataEnum,
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<Statement>> {
+    let mut untagged_union = UntaggedUnion::new(ident.to_string());
+    untagged_union.unknown_keys = attrs.unknown_keys;
+    untagged_union.add_variants(extract_variants(e, attrs)?);
+    let statements = vec![Statement::Export(Item::UntaggedUnion(untagged_union))];
+    Ok(statements)
+}
+
+fn extract_variants(
+    data_enum: &DataEnum,
+    attrs: &ContainerAttrs,
+) -> syn::Result<Vec<UnionVariant>> {
+    error::collect(data_enum.variants.iter().map(|vari| {
+        let variant_attrs = VariantAttrs::from_attrs(&vari.attrs, attrs.mode)?;
+        let ident = variant_attrs.name(&vari.ident, &attrs.rename_all, attrs.mode);
+        // a variant's own `rename_all` wins over the enum's `rename_all_fields`
+        let rename_fields = match variant_attrs.rename_all.get(attrs.mode) {
+            RenameRule::None => *attrs.rename_all_fields.get(attrs.mode),
+            rule => *rule,
+        };
+        let variant = match &vari.fields {
+            Fields::Named(fields_named) => {
+                UnionVariant::from_syn_fields_named(ident, fields_named, rename_fields, attrs.mode)
             }
-        })
-        .collect()
+            Fields::Unnamed(fields) => {
+                UnionVariant::from_syn_fields_unnamed(ident, fields, attrs.mode)
+            }
+            Fields::Unit => Ok(UnionVariant::from_unit(ident)),
+        };
+        variant.map(|variant| variant.with_aliases(variant_attrs.aliases(attrs.mode)))
+    }))
 }
 
-fn as_ty(ty: &Type) -> Result<Ty, String> {
+fn as_ty(ty: &Type) -> syn::Result<Ty> {
     match ty {
+        Type::Path(p) if p.qself.is_some() => Err(Error::new_spanned(
+            ty,
+            "associated types can't be described, use the type they resolve to",
+        )),
+        // qualified paths, like `std::vec::Vec<T>` or `crate::models::User`, are
+        // recognised by their last segment
         Type::Path(p) => {
-            // is it a raw ident, like 'u8'
-            if let Some(ident) = p.path.get_ident() {
-                return Ok(rust_ident_to_ty(ident.to_string()));
-            }
-
-            for x in &p.path.segments {
+            if let Some(x) = p.path.segments.last() {
                 match &x.arguments {
+                    // a plain name, like 'u8' or 'User'
                     PathArguments::None => {
-                        todo!("PathArguments::None")
+                        return Ok(match rust_ident_to_ty(x.ident.to_string()) {
+                            Ty::Reference { args, .. } => Ty::Reference {
+                                ident: reference_name(&p.path),
+                                args,
+                                rust: Some(quote!(#p)),
+                            },
+                            ty => ty,
+                        });
                     }
                     PathArguments::AngleBracketed(o) => {
                         let ident = x.ident.to_string();
-                        let first_arg = o.args.first();
+                        // lifetimes, as in `Cow<'a, str>`, don't affect the schema
+                        let first_arg = o
+                            .args
+                            .iter()
+                            .find(|arg| matches!(arg, GenericArgument::Type(_)));
 
                         match (ident.as_str(), first_arg) {
-                            ("Vec" | "Option", Some(arg1)) => {
-                                if let Ok(inner) = ty_from_generic_argument(arg1) {
-                                    if ident == "Vec" {
-                                        return Ok(Ty::seq(inner));
-                                    }
-                                    if ident == "Option" {
-                                        return Ok(Ty::optional(inner));
-                                    }
+                            ("Vec", Some(arg1)) => {
+                                return Ok(Ty::seq(ty_from_generic_argument(arg1)?));
+                            }
+                            ("Option", Some(arg1)) => {
+                                let inner = ty_from_generic_argument(arg1)?;
+                                return Ok(match Config::global().options {
+                                    Options::Serde => Ty::nullable(inner),
+                                    Options::Optional => Ty::optional(inner),
+                                });
+                            }
+                            (
+                                "VecDeque" | "LinkedList" | "BinaryHeap",
+                                Some(GenericArgument::Type(inner)),
+                            ) => {
+                                return Ok(Ty::seq(as_ty(inner)?));
+                            }
+                            (
+                                "HashSet" | "BTreeSet" | "IndexSet",
+                                Some(GenericArgument::Type(inner)),
+                            ) => {
+                                return Ok(Ty::set(as_ty(inner)?, Config::global().unique_sets));
+                            }
+                            // smart pointers and wrappers are serialized as the value they hold
+                            (
+                                "Box" | "Cow" | "Rc" | "Arc" | "RefCell" | "Cell" | "Mutex"
+                                | "RwLock",
+                                Some(GenericArgument::Type(inner)),
+                            ) => {
+                                return as_ty(inner);
+                            }
+                            // serialized as a unit, whatever the type parameter
+                            ("PhantomData", _) => return Ok(Ty::ZodNull),
+                            ("HashMap" | "BTreeMap" | "IndexMap", Some(_)) => {
+                                let mut args = o.args.iter();
+                                if let (Some(GenericArgument::Type(key)), Some(value)) =
+                                    (args.next(), args.next())
+                                {
+                                    let key = record_key_ty(key)?;
+                                    let value = ty_from_generic_argument(value)?;
+                                    return Ok(Ty::record(key, value));
                                 }
                             }
-                            _a => todo!("support more idents like: {}", ident),
+                            // any other type with parameters is expected to be generic
+                            // and have its own `#[serde_zod::codegen]`
+                            _ => {
+                                let args = o
+                                    .args
+                                    .iter()
+                                    .filter(|arg| matches!(arg, GenericArgument::Type(_)))
+                                    .map(ty_from_generic_argument)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                return Ok(Ty::Reference {
+                                    ident: reference_name(&p.path),
+                                    args,
+                                    rust: Some(quote!(#p)),
+                                });
+                            }
                         }
                     }
                     PathArguments::Parenthesized(_) => {
-                        println!("para")
+                        return Err(Error::new_spanned(
+                            x,
+                            "closures and functions can't be serialized",
+                        ));
                     }
                 }
             }
 
-            Err("could not get identifier".into())
+            Err(Error::new_spanned(ty, "unsupported type"))
+        }
+        // `()` is written as `null`
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Ok(Ty::ZodNull),
+        Type::Tuple(tuple) => Ok(Ty::Tuple(error::collect(tuple.elems.iter().map(as_ty))?)),
+        Type::Array(arr) => {
+            let inner = as_ty(&arr.elem)?;
+            match &arr.len {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(len), ..
+                }) => {
+                    let len = len.base10_parse::<usize>()?;
+                    Ok(Ty::array(inner, len))
+                }
+                // the length is a const or expression that can't be evaluated here
+                _ => Ok(Ty::seq(inner)),
+            }
+        }
+        // borrowed values are serialized as the value itself, so `&[T]` like `Vec<T>`
+        Type::Reference(reference) => as_ty(&reference.elem),
+        Type::Slice(slice) => Ok(Ty::seq(as_ty(&slice.elem)?)),
+        // `(T)`, and the invisible groups left by `macro_rules!` expansions
+        Type::Paren(paren) => as_ty(&paren.elem),
+        Type::Group(group) => as_ty(&group.elem),
+        _ => Err(Error::new_spanned(ty, "unsupported type")),
+    }
+}
+
+/// Whether a type is named without its module, like `Node` or `self::Node`
+fn unqualified(rust: &proc_macro2::TokenStream) -> bool {
+    match syn::parse2::<syn::Path>(rust.clone()) {
+        Ok(path) => match path.segments.len() {
+            1 => path.leading_colon.is_none(),
+            2 => path.leading_colon.is_none() && path.segments[0].ident == "self",
+            _ => false,
+        },
+        Err(_) => false,
+    }
+}
+
+/// The name another schema is exported as: the type's own name, or with
+/// `SERDE_ZOD_PATHS=qualified` its module path as well, like `models.User`
+fn reference_name(path: &syn::Path) -> String {
+    match Config::global().paths {
+        Paths::Last => path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        Paths::Qualified => path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .filter(|segment| !matches!(segment.as_str(), "crate" | "self" | "super"))
+            .collect::<Vec<_>>()
+            .join("."),
+    }
+}
+
+/// serde_json writes every map key as a JSON string, so keys that aren't
+/// strings already (integers, for example) are validated by their string form
+fn record_key_ty(ty: &Type) -> syn::Result<Ty> {
+    // the integer types are all `z.number()` once described, unless `SERDE_ZOD_INTEGERS` is set
+    if let Type::Path(p) = ty {
+        let segment = p.path.segments.last();
+        if let Some(int) = segment.and_then(|s| IntKind::from_ident(&s.ident.to_string())) {
+            return Ok(Ty::IntegerString {
+                signed: int.signed,
+                non_zero: int.non_zero,
+            });
         }
-        _ => Err(String::from("unknown")),
+    }
+    match as_ty(ty)? {
+        Ty::ZodNumber => Ok(Ty::FloatString),
+        Ty::ZodBoolean => Ok(Ty::BooleanString),
+        // another type's key form comes from its `ZodKeySchema`, see `key_form`
+        key @ (Ty::ZodString | Ty::ZodChar | Ty::IntegerString { .. } | Ty::Reference { .. }) => {
+            Ok(key)
+        }
+        _ => Err(Error::new_spanned(
+            ty,
+            "serde_json can't write this as a map key, which must be a string, number, \
+             bool or unit variant",
+        )),
     }
 }
 
-fn ty_from_generic_argument(a: &GenericArgument) -> Result<Ty, String> {
+/// The schema of what serde_json writes for the type as a map key, if it can be one: `None`
+/// for a unit enum, which is written by its variant names like its own schema, and the string
+/// form of what a newtype holds
+fn key_form(
+    data: &Data,
+    statements: &[Statement],
+    attrs: &ContainerAttrs,
+) -> Option<Option<String>> {
+    if statements
+        .iter()
+        .any(|statement| matches!(statement, Statement::Export(Item::Enum(_))))
+    {
+        return Some(None);
+    }
+    let field = match data {
+        Data::Struct(st) => match &st.fields {
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => unnamed.unnamed.first(),
+            fields if attrs.transparent => fields.iter().find(|field| {
+                FieldAttrs::from_attrs(&field.attrs, attrs.mode)
+                    .is_ok_and(|field_attrs| !field_attrs.skipped(attrs.mode))
+            }),
+            _ => None,
+        },
+        _ => None,
+    }?;
+    match record_key_ty(&field.ty).ok()? {
+        // the other type's key form isn't known here
+        Ty::Reference { .. } => None,
+        ty => ty.as_string().ok().map(Some),
+    }
+}
+
+/// serde writes a newtype (a single unnamed field) as its inner value, and
+/// anything with more unnamed fields as an array of the ones that aren't skipped
+fn unnamed_fields_ty(unnamed: &FieldsUnnamed, mode: Mode) -> syn::Result<Ty> {
+    if let Some(field) = unnamed
+        .unnamed
+        .first()
+        .filter(|_| unnamed.unnamed.len() == 1)
+    {
+        return field_ty(field, &FieldAttrs::from_attrs(&field.attrs, mode)?);
+    }
+    let tys = error::collect(unnamed.unnamed.iter().map(|field| {
+        let field_attrs = FieldAttrs::from_attrs(&field.attrs, mode)?;
+        if field_attrs.skipped(mode) {
+            return Ok(None);
+        }
+        field_ty(field, &field_attrs).map(Some)
+    }))?;
+    Ok(Ty::Tuple(tys.into_iter().flatten().collect()))
+}
+
+/// The field's type, unless `#[serde_zod(schema = "...")]` describes it instead
+fn field_ty(field: &syn::Field, attrs: &FieldAttrs) -> syn::Result<Ty> {
+    let ty = match &attrs.schema {
+        Some(schema) => Ty::Custom(schema.clone()),
+        None => as_ty(&field.ty)?,
+    };
+    if attrs.flatten {
+        flattenable(&ty, &field.ty)?;
+    }
+    Ok(ty)
+}
+
+/// zod only merges object schemas, so a flattened field must be a struct or a map, or an
+/// `Option` of either. Whether a struct's schema is a plain `z.object()` is only known to its
+/// own `ZodSchema`, so that's checked by the compiler through `ZodObjectSchema` instead
+fn flattenable(ty: &Ty, rust: &Type) -> syn::Result<()> {
+    match ty {
+        Ty::Optional(inner) | Ty::Nullable(inner) => flattenable(inner, rust),
+        Ty::Record { .. } | Ty::Reference { .. } | Ty::Custom(_) => Ok(()),
+        _ => Err(Error::new_spanned(
+            rust,
+            "only a struct or map can be flattened, as zod can only merge object schemas",
+        )),
+    }
+}
+
+/// The Rust type of a flattened struct, whose schema is merged into this one
+fn merged_rust(ty: &Ty) -> Option<&proc_macro2::TokenStream> {
+    match ty {
+        Ty::Optional(ty) | Ty::Nullable(ty) | Ty::Lazy { ty, .. } => merged_rust(ty),
+        Ty::Reference { rust, .. } => rust.as_ref(),
+        _ => None,
+    }
+}
+
+fn ty_from_generic_argument(a: &GenericArgument) -> syn::Result<Ty> {
     match a {
         GenericArgument::Type(ty) => as_ty(ty),
-        _ => Err("only Types are supported as generic arguments".into()),
+        _ => Err(Error::new_spanned(
+            a,
+            "only types are supported as generic arguments",
+        )),
     }
 }
 
@@ -265,34 +1031,24 @@ fn quote<A: AsRef<str>>(a: A) -> String {
     format!("\"{}\"", a.as_ref())
 }
 
-fn serde_attrs(attrs: &[Attribute]) -> HashMap<String, String> {
-    attrs
-        .iter()
-        .filter(|att| att.path.get_ident().filter(|v| *v == "serde").is_some())
-        .filter_map(|item| {
-            let parsed = item.parse_meta().expect("parse meta on attribute");
-            if let Meta::List(l) = parsed {
-                for nested in l.nested {
-                    match nested {
-                        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                            path,
-                            lit: syn::Lit::Str(str),
-                            ..
-                        })) => {
-                            if let Some(ident) = path.get_ident().map(|x| x.to_string()) {
-                                return Some((ident, str.value()));
-                            }
-                        }
-                        _ => todo!("?"),
-                    }
-                }
-            }
-            None
-        })
-        .collect()
+/// Keys such as `page-url` (from `rename_all = "kebab-case"`) must be quoted in an object literal
+fn object_key<A: AsRef<str>>(a: A) -> String {
+    let key = a.as_ref();
+    let mut chars = key.chars();
+    let is_identifier = chars
+        .next()
+        .filter(|c| c.is_ascii_alphabetic() || *c == '_' || *c == '$')
+        .is_some()
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        key.to_string()
+    } else {
+        quote(key)
+    }
 }
 
-fn has_serde_derive(attrs: &[Attribute]) -> bool {
+/// The serde traits, "Serialize" and/or "Deserialize", that are derived on the item
+fn serde_derives(attrs: &[Attribute]) -> Vec<String> {
     attrs
         .iter()
         .filter_map(|attr| attr.parse_meta().ok())
@@ -303,7 +1059,7 @@ fn has_serde_derive(attrs: &[Attribute]) -> bool {
             None
         })
         .flatten()
-        .any(|x| match x {
+        .filter_map(|x| match x {
             NestedMeta::Meta(Meta::Path(path)) => {
                 // first is 'serde'
                 let first = path.segments.first().filter(|x| x.ident == "serde");
@@ -312,21 +1068,68 @@ fn has_serde_derive(attrs: &[Attribute]) -> bool {
                 let sub = path
                     .segments
                     .iter()
-                    .any(|s| s.ident == "Serialize" || s.ident == "Deserialize");
+                    .find(|s| s.ident == "Serialize" || s.ident == "Deserialize");
 
-                matches!((first, sub), (Some(..), true))
+                match (first, sub) {
+                    (Some(..), Some(sub)) => Some(sub.ident.to_string()),
+                    _ => None,
+                }
             }
-            _ => false,
+            _ => None,
         })
+        .collect()
 }
 
 fn rust_ident_to_ty<A: AsRef<str>>(raw_ident: A) -> Ty {
-    println!("{}", raw_ident.as_ref());
+    if let Some(int) = IntKind::from_ident(raw_ident.as_ref()) {
+        return int.ty(Config::global());
+    }
     match raw_ident.as_ref() {
-        "u8" | "u32" | "u64" | "usize" | "i8" | "i32" | "i64" | "isize" | "f32" | "f64" => {
-            Ty::ZodNumber
-        }
-        "String" => Ty::ZodString,
-        ident => Ty::Reference(ident.to_string()),
+        "f32" | "f64" => Ty::ZodNumber,
+        "bool" => Ty::ZodBoolean,
+        "char" => Ty::ZodChar,
+        "String" | "str" => Ty::ZodString,
+        ident => Ty::reference(ident),
     }
 }
+
+#[test]
+fn test_flattenable() {
+    let flattenable = |ty: Type| flattenable(&as_ty(&ty).unwrap(), &ty);
+    assert!(flattenable(syn::parse_quote!(Meta)).is_ok());
+    assert!(flattenable(syn::parse_quote!(Option<HashMap<String, u8>>)).is_ok());
+    assert!(flattenable(syn::parse_quote!(String)).is_err());
+    assert!(flattenable(syn::parse_quote!(Vec<Meta>)).is_err());
+}
+
+#[test]
+fn test_record_key_ty() -> Result<(), std::fmt::Error> {
+    let key = |ty: Type| record_key_ty(&ty).map(|key| key.as_string());
+    assert_eq!(
+        key(syn::parse_quote!(f64)).unwrap()?,
+        r"z.string().regex(/^-?\d+(\.\d+)?(e-?\d+)?$/)"
+    );
+    assert_eq!(
+        key(syn::parse_quote!(std::num::NonZeroI32)).unwrap()?,
+        r"z.string().regex(/^-?[1-9]\d*$/)"
+    );
+    assert_eq!(key(syn::parse_quote!(UserId)).unwrap()?, "UserId");
+    for ty in [
+        syn::parse_quote!((u8, u8)),
+        syn::parse_quote!(Vec<String>),
+        syn::parse_quote!(Option<String>),
+    ] {
+        assert!(key(ty).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unqualified() {
+    assert!(unqualified(&quote!(Node)));
+    assert!(unqualified(&quote!(self::Node)));
+    assert!(unqualified(&quote!(Tree<T>)));
+    assert!(!unqualified(&quote!(a::Node)));
+    assert!(!unqualified(&quote!(crate::Node)));
+    assert!(!unqualified(&quote!(::Node)));
+}
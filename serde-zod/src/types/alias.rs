@@ -0,0 +1,55 @@
+use crate::printer::Printer;
+use crate::Field;
+use std::fmt::Write;
+
+/// The `alias: "key"` pairs for every field with a `#[serde(alias = "..")]`, as an object
+/// literal like `{ href: "url" }`, or `None` when no field has an alias
+pub fn alias_lookup(fields: &[Field]) -> Option<String> {
+    let pairs = fields
+        .iter()
+        .flat_map(|field| {
+            field.aliases.iter().map(move |alias| {
+                format!(
+                    "{}: {}",
+                    crate::object_key(alias),
+                    crate::quote(&field.ident)
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    if pairs.is_empty() {
+        None
+    } else {
+        Some(format!("{{ {} }}", pairs.join(", ")))
+    }
+}
+
+/// A `z.object` only knows a single key per field, so the schema `inner` is wrapped
+/// to rename any aliased keys (as resolved by the `lookup` expression) before validation
+pub fn print_with_aliases(
+    lookup: &str,
+    inner: &str,
+    target: &mut String,
+) -> Result<(), std::fmt::Error> {
+    let mut printer = Printer::new();
+    printer.writeln("z.preprocess(")?;
+    printer.indent();
+    printer.writeln("(input: any) => {")?;
+    printer.indent();
+    printer.writeln(format!(
+        "const aliases: Record<string, string> = {}",
+        lookup
+    ))?;
+    printer.writeln(
+        "if (input === null || typeof input !== \"object\" || Array.isArray(input)) return input",
+    )?;
+    printer.writeln(
+        "return Object.fromEntries(Object.entries(input).map(([key, value]) => [aliases[key] ?? key, value]))",
+    )?;
+    printer.dedent();
+    printer.writeln("},")?;
+    printer.write(inner)?;
+    printer.dedent();
+    printer.writeln(")")?;
+    write!(target, "{}", printer.dump())
+}
@@ -0,0 +1,170 @@
+use crate::config::{Config, LargeIntegers};
+use crate::types::ty::Ty;
+
+/// The Rust integer types, including the `NonZero` ones
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IntKind {
+    pub bits: u32,
+    pub signed: bool,
+    pub non_zero: bool,
+}
+
+impl IntKind {
+    pub fn from_ident(ident: &str) -> Option<Self> {
+        let (ident, non_zero) = match ident.strip_prefix("NonZero") {
+            Some(rest) => (rest.to_ascii_lowercase(), true),
+            None => (ident.to_string(), false),
+        };
+        let (signed, bits) = match (ident.strip_prefix('u'), ident.strip_prefix('i')) {
+            (Some(bits), _) => (false, bits),
+            (_, Some(bits)) => (true, bits),
+            _ => return None,
+        };
+        let bits = match bits {
+            // assume a 64 bit target, which is where a number stops fitting a JS number anyway
+            "size" => 64,
+            "8" | "16" | "32" | "64" | "128" => bits.parse().ok()?,
+            _ => return None,
+        };
+        Some(Self {
+            bits,
+            signed,
+            non_zero,
+        })
+    }
+    pub fn min(&self) -> String {
+        match (self.signed, self.non_zero) {
+            (false, false) => "0".into(),
+            (false, true) => "1".into(),
+            (true, _) => format!("-{}", 1u128 << (self.bits - 1)),
+        }
+    }
+    pub fn max(&self) -> String {
+        match (self.signed, self.bits) {
+            (false, 128) => u128::MAX.to_string(),
+            (false, bits) => ((1u128 << bits) - 1).to_string(),
+            (true, bits) => ((1u128 << (bits - 1)) - 1).to_string(),
+        }
+    }
+    /// Whether zero has to be rejected on top of `min` and `max`, which is the case for the
+    /// signed `NonZero` types
+    pub fn zero_in_bounds(&self) -> bool {
+        self.signed && self.non_zero
+    }
+    /// Anything wider than 32 bits can hold values a JS number can't represent exactly
+    pub fn is_large(&self) -> bool {
+        self.bits > 32
+    }
+    pub fn ty(&self, config: &Config) -> Ty {
+        match (self.is_large(), config.large_integers) {
+            (true, LargeIntegers::BigInt) => Ty::BigInt {
+                min: format!("{}n", self.min()),
+                max: format!("{}n", self.max()),
+                non_zero: self.zero_in_bounds(),
+            },
+            (true, LargeIntegers::String) => Ty::IntegerString {
+                signed: self.signed,
+                non_zero: self.non_zero,
+            },
+            _ if !config.integers => Ty::ZodNumber,
+            (true, LargeIntegers::Number) => Ty::Int {
+                min: if self.signed {
+                    "Number.MIN_SAFE_INTEGER".into()
+                } else {
+                    self.min()
+                },
+                max: "Number.MAX_SAFE_INTEGER".into(),
+                non_zero: self.zero_in_bounds(),
+            },
+            (false, _) => Ty::Int {
+                min: self.min(),
+                max: self.max(),
+                non_zero: self.zero_in_bounds(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::Print;
+
+    fn print(ident: &str, config: &Config) -> String {
+        let kind = IntKind::from_ident(ident).expect("integer");
+        kind.ty(config).as_string().expect("print")
+    }
+
+    #[test]
+    fn test_from_ident() {
+        assert_eq!(IntKind::from_ident("String"), None);
+        assert_eq!(IntKind::from_ident("f64"), None);
+        // names that don't start with an ASCII character
+        assert_eq!(IntKind::from_ident("Über"), None);
+        assert_eq!(IntKind::from_ident("NonZeroÜ8"), None);
+        assert_eq!(
+            IntKind::from_ident("NonZeroU16"),
+            Some(IntKind {
+                bits: 16,
+                signed: false,
+                non_zero: true
+            })
+        );
+        let signed = IntKind::from_ident("NonZeroI8").expect("integer");
+        assert!(signed.signed && signed.non_zero);
+        assert!(signed.zero_in_bounds());
+        assert!(!IntKind::from_ident("NonZeroU8").unwrap().zero_in_bounds());
+    }
+
+    #[test]
+    fn test_bounds() {
+        let config = Config {
+            integers: true,
+            ..Config::default()
+        };
+        assert_eq!(print("u8", &config), "z.number().int().min(0).max(255)");
+        assert_eq!(
+            print("i32", &config),
+            "z.number().int().min(-2147483648).max(2147483647)"
+        );
+        assert_eq!(
+            print("NonZeroU32", &config),
+            "z.number().int().min(1).max(4294967295)"
+        );
+        assert_eq!(
+            print("NonZeroI8", &config),
+            r#"z.number().int().min(-128).max(127).refine((n) => n !== 0, "Expected a non-zero integer")"#
+        );
+        assert_eq!(
+            print("u64", &config),
+            "z.number().int().min(0).max(Number.MAX_SAFE_INTEGER)"
+        );
+        assert_eq!(print("u8", &Config::default()), "z.number()");
+    }
+
+    #[test]
+    fn test_large_integers() {
+        let bigint = Config {
+            large_integers: LargeIntegers::BigInt,
+            ..Config::default()
+        };
+        assert_eq!(
+            print("i64", &bigint),
+            "z.bigint().min(-9223372036854775808n).max(9223372036854775807n)"
+        );
+        assert_eq!(
+            print("NonZeroI64", &bigint),
+            r#"z.bigint().min(-9223372036854775808n).max(9223372036854775807n).refine((n) => n !== 0n, "Expected a non-zero integer")"#
+        );
+        assert_eq!(print("u32", &bigint), "z.number()");
+        let string = Config {
+            large_integers: LargeIntegers::String,
+            ..Config::default()
+        };
+        assert_eq!(print("u128", &string), r"z.string().regex(/^\d+$/)");
+        assert_eq!(
+            print("NonZeroI128", &string),
+            r"z.string().regex(/^-?[1-9]\d*$/)"
+        );
+    }
+}
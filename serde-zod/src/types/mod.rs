@@ -1,6 +1,11 @@
+pub mod alias;
 pub mod import;
+pub mod int;
+pub mod named_ty;
 pub mod object;
 pub mod tagged_union;
+pub mod ts;
 pub mod ty;
 pub mod union;
+pub mod untagged_union;
 pub mod zod_enum;
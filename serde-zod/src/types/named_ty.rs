@@ -0,0 +1,17 @@
+use crate::printer::Print;
+use crate::types::ty::Ty;
+use std::fmt::Write;
+
+/// An exported item that is just a type, like a newtype struct `struct Id(String)` which
+/// serde writes as its inner value
+#[derive(Debug)]
+pub struct NamedTy {
+    pub ident: String,
+    pub ty: Ty,
+}
+
+impl Print for NamedTy {
+    fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
+        writeln!(x, "{}", self.ty.as_string()?)
+    }
+}
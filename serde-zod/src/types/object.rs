@@ -1,4 +1,6 @@
 use crate::printer::Printer;
+use crate::types::alias::{alias_lookup, print_with_aliases};
+use crate::types::ty::Ty;
 use crate::{Field, Print};
 use std::fmt::Write;
 
@@ -6,34 +8,93 @@ use std::fmt::Write;
 pub struct Object {
     pub ident: String,
     pub fields: Vec<Field>,
+    pub unknown_keys: UnknownKeys,
 }
 
 impl Print for Object {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
-        print_obj(&self.fields, x)
+        print_obj(&self.fields, self.unknown_keys, x)
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct InlineObject {
     pub fields: Vec<Field>,
+    pub unknown_keys: UnknownKeys,
 }
 
 impl Print for InlineObject {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
-        print_obj(&self.fields, x)
+        print_obj(&self.fields, self.unknown_keys, x)
     }
 }
 
-fn print_obj(fields: &[Field], target: &mut String) -> Result<(), std::fmt::Error> {
+/// What an object schema does with keys it doesn't declare
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UnknownKeys {
+    /// zod's default, unknown keys are dropped from the parsed value
+    #[default]
+    Strip,
+    /// unknown keys fail validation, like `#[serde(deny_unknown_fields)]`
+    Strict,
+    /// unknown keys are kept in the parsed value
+    Passthrough,
+}
+
+impl UnknownKeys {
+    /// Comes last, as `.merge()` takes the setting of the merged object
+    pub fn suffix(self) -> &'static str {
+        match self {
+            UnknownKeys::Strip => "",
+            UnknownKeys::Strict => ".strict()",
+            UnknownKeys::Passthrough => ".passthrough()",
+        }
+    }
+}
+
+fn print_obj(
+    fields: &[Field],
+    unknown_keys: UnknownKeys,
+    target: &mut String,
+) -> Result<(), std::fmt::Error> {
     let mut printer = Printer::new();
     printer.writeln("z.object({")?;
     printer.indent();
-    for field in fields {
+    for field in fields.iter().filter(|field| !field.flatten) {
         printer.line(field.as_string()?);
     }
     printer.join_lines(',')?;
     printer.dedent();
-    printer.writeln("})")?;
-    write!(target, "{}", printer.dump())
+    printer.writeln(format!(
+        "}}){}{}",
+        flattened(fields)?,
+        unknown_keys.suffix()
+    ))?;
+    match alias_lookup(fields) {
+        Some(lookup) => print_with_aliases(&lookup, &printer.dump(), target),
+        None => write!(target, "{}", printer.dump()),
+    }
+}
+
+/// `#[serde(flatten)]` fields have no key of their own: a flattened struct contributes its
+/// fields, and a flattened map takes every key that isn't otherwise known
+pub fn flattened(fields: &[Field]) -> Result<String, std::fmt::Error> {
+    let mut suffix = String::new();
+    for field in fields.iter().filter(|field| field.flatten) {
+        // a flattened `None` adds no keys at all
+        let (ty, optional) = match &field.ty {
+            Ty::Optional(inner) | Ty::Nullable(inner) => match inner.as_ref() {
+                Ty::Nullable(inner) => (inner.as_ref(), true),
+                inner => (inner, true),
+            },
+            ty => (ty, false),
+        };
+        match ty {
+            // nor does an empty map
+            Ty::Record { value, .. } => write!(suffix, ".catchall({})", value.as_string()?)?,
+            ty if optional => write!(suffix, ".merge({}.partial())", ty.as_string()?)?,
+            ty => write!(suffix, ".merge({})", ty.as_string()?)?,
+        }
+    }
+    Ok(suffix)
 }
@@ -1,13 +1,20 @@
 use crate::printer::Printer;
+use crate::types::alias::{alias_lookup, print_with_aliases};
+use crate::types::object::{flattened, InlineObject, UnknownKeys};
+use crate::types::ty::Ty;
 use crate::union::{UnionVariant, UnionVariantFields};
-use crate::Print;
+use crate::{Field, Print};
 use std::fmt::Write;
 
 #[derive(Debug)]
 pub struct TaggedUnion {
     pub ident: String,
     pub tag: String,
+    /// when set, variant fields are nested under this key rather than sitting beside the tag
+    pub content: Option<String>,
     pub variants: Vec<UnionVariant>,
+    /// applied to each variant's object
+    pub unknown_keys: UnknownKeys,
 }
 
 impl TaggedUnion {
@@ -15,12 +22,55 @@ impl TaggedUnion {
         Self {
             ident: ident.into(),
             tag: tag.into(),
+            content: None,
             variants: vec![],
+            unknown_keys: UnknownKeys::Strip,
+        }
+    }
+    /// `#[serde(tag = "t", content = "c")]`
+    pub fn adjacent(
+        ident: impl Into<String>,
+        tag: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            content: Some(content.into()),
+            ..Self::new(ident, tag)
         }
     }
     pub fn add_variants(&mut self, variants: Vec<UnionVariant>) {
         self.variants.extend(variants);
     }
+    /// Field aliases differ per variant, so they are looked up by the value of the tag
+    fn alias_lookup(&self) -> Option<String> {
+        // nested fields under `content` handle their own aliases
+        if self.content.is_some() {
+            return None;
+        }
+        let by_tag = self
+            .variants
+            .iter()
+            .filter_map(|variant| match &variant.fields {
+                UnionVariantFields::Named(fields) => {
+                    alias_lookup(fields).map(|lookup| (variant, lookup))
+                }
+                _ => None,
+            })
+            .flat_map(|(variant, lookup)| {
+                variant
+                    .names()
+                    .map(move |name| format!("{}: {}", crate::object_key(name), lookup))
+            })
+            .collect::<Vec<_>>();
+        if by_tag.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "({{ {} }} as Record<string, Record<string, string>>)[input?.[{}]] ?? {{}}",
+            by_tag.join(", "),
+            crate::quote(&self.tag)
+        ))
+    }
 }
 
 impl Print for TaggedUnion {
@@ -32,31 +82,54 @@ impl Print for TaggedUnion {
         ))?;
         printer.indent();
         for x in &self.variants {
-            printer.writeln("z.object({")?;
-            printer.indent();
-            printer.line(format!(
-                "{}: z.literal({})",
-                self.tag,
-                crate::quote(&x.ident)
-            ));
-            match &x.fields {
-                UnionVariantFields::Unit => {}
-                UnionVariantFields::Named(fields) => {
-                    for field in fields {
-                        printer.line(field.as_string()?);
+            for name in x.names() {
+                printer.writeln("z.object({")?;
+                printer.indent();
+                printer.line(format!(
+                    "{}: z.literal({})",
+                    crate::object_key(&self.tag),
+                    crate::quote(name)
+                ));
+                match (&self.content, &x.fields) {
+                    (_, UnionVariantFields::Unit) => {}
+                    (None, UnionVariantFields::Named(fields)) => {
+                        for field in fields.iter().filter(|field| !field.flatten) {
+                            printer.line(field.as_string()?);
+                        }
+                    }
+                    (None, UnionVariantFields::Unnamed(_)) => {
+                        // newtype variants are flattened fields by now, and serde rejects
+                        // tuple variants in an internally tagged enum
+                    }
+                    (Some(content), UnionVariantFields::Named(fields)) => {
+                        let inline = Ty::InlineObject(InlineObject {
+                            fields: fields.clone(),
+                            unknown_keys: self.unknown_keys,
+                        });
+                        printer.line(Field::new(content, inline).as_string()?);
+                    }
+                    (Some(content), UnionVariantFields::Unnamed(ty)) => {
+                        printer.line(Field::new(content, ty.clone()).as_string()?);
                     }
                 }
-                UnionVariantFields::Unnamed(_) => {
-                    // not allowed via serde rules
+                printer.join_lines(',')?;
+                printer.dedent();
+                match (&self.content, &x.fields) {
+                    (None, UnionVariantFields::Named(fields)) => printer.writeln(format!(
+                        "}}){}{},",
+                        flattened(fields)?,
+                        self.unknown_keys.suffix()
+                    ))?,
+                    _ => printer.writeln(format!("}}){},", self.unknown_keys.suffix()))?,
                 }
             }
-            printer.join_lines(',')?;
-            printer.dedent();
-            printer.writeln("}),")?;
         }
         printer.dedent();
         printer.writeln("])")?;
 
-        write!(x, "{}", printer.dump())
+        match self.alias_lookup() {
+            Some(lookup) => print_with_aliases(&lookup, &printer.dump(), x),
+            None => write!(x, "{}", printer.dump()),
+        }
     }
 }
@@ -0,0 +1,180 @@
+//! The TypeScript type a schema parses to, written out for recursive schemas since
+//! TypeScript can't infer the type of a value that refers to itself
+
+use crate::lazy::Kind;
+use crate::types::object::UnknownKeys;
+use crate::types::ty::Ty;
+use crate::union::{UnionVariant, UnionVariantFields};
+use crate::zod::{Field, Item};
+use crate::{object_key, quote};
+
+/// `name` is the type being written, which refers to itself by name rather than through
+/// `z.infer`
+pub fn item_type(item: &Item, name: &str) -> String {
+    match item {
+        Item::Enum(en) => en
+            .variants
+            .iter()
+            .flat_map(|variant| std::iter::once(&variant.ident).chain(&variant.aliases))
+            .map(quote)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Item::Union(union) => variants(&union.variants, |variant, key| {
+            let value = match &variant.fields {
+                UnionVariantFields::Unit => return quote(key),
+                UnionVariantFields::Named(fields) => {
+                    object_type(vec![], fields, union.unknown_keys, name)
+                }
+                UnionVariantFields::Unnamed(ty) => ty_type(ty, name),
+            };
+            format!("{{ {}: {} }}", object_key(key), value)
+        }),
+        Item::TaggedUnion(union) => variants(&union.variants, |variant, value| {
+            let tag = format!("{}: {}", object_key(&union.tag), quote(value));
+            match (&union.content, &variant.fields) {
+                // a newtype variant's payload is a flattened field by now
+                (_, UnionVariantFields::Unit) | (None, UnionVariantFields::Unnamed(_)) => {
+                    object_type(vec![tag], &[], union.unknown_keys, name)
+                }
+                (None, UnionVariantFields::Named(fields)) => {
+                    object_type(vec![tag], fields, union.unknown_keys, name)
+                }
+                (Some(content), UnionVariantFields::Named(fields)) => {
+                    let inner = object_type(vec![], fields, union.unknown_keys, name);
+                    let content = format!("{}: {}", object_key(content), inner);
+                    object_type(vec![tag, content], &[], union.unknown_keys, name)
+                }
+                (Some(content), UnionVariantFields::Unnamed(ty)) => {
+                    let content = format!("{}: {}", object_key(content), ty_type(ty, name));
+                    object_type(vec![tag, content], &[], union.unknown_keys, name)
+                }
+            }
+        }),
+        Item::UntaggedUnion(union) => union
+            .variants
+            .iter()
+            .map(|variant| match &variant.fields {
+                UnionVariantFields::Unit => "null".to_string(),
+                UnionVariantFields::Named(fields) => {
+                    object_type(vec![], fields, union.unknown_keys, name)
+                }
+                UnionVariantFields::Unnamed(ty) => ty_type(ty, name),
+            })
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Item::Object(object) => object_type(vec![], &object.fields, object.unknown_keys, name),
+        Item::Ty(named) => ty_type(&named.ty, name),
+    }
+}
+
+fn variants(
+    variants: &[UnionVariant],
+    variant_type: impl Fn(&UnionVariant, &str) -> String,
+) -> String {
+    // every alias of a variant is a representation of its own
+    variants
+        .iter()
+        .flat_map(|variant| variant.names().map(move |name| (variant, name)))
+        .map(|(variant, variant_name)| variant_type(variant, variant_name))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+pub fn ty_type(ty: &Ty, name: &str) -> String {
+    match ty {
+        Ty::ZodNumber | Ty::Int { .. } => "number".into(),
+        Ty::ZodString | Ty::ZodChar | Ty::IntegerString { .. } | Ty::FloatString => "string".into(),
+        Ty::BooleanString => r#""true" | "false""#.into(),
+        Ty::Literal(lit) => quote(lit),
+        Ty::ZodBoolean => "boolean".into(),
+        Ty::ZodNull => "null".into(),
+        Ty::ZodNever => "never".into(),
+        Ty::BigInt { .. } => "bigint".into(),
+        // whatever the given schema parses to, which can't be known without TypeScript
+        Ty::Custom(_) => "unknown".into(),
+        Ty::Reference { ident, args, .. } if args.is_empty() && ident == name => ident.clone(),
+        Ty::Reference { ident, args, .. } => reference_type(ident, args, name),
+        // never through `z.lazy`, as types can refer to each other in any order
+        Ty::Dependency {
+            index, prefix, ty, ..
+        } => match ty.as_ref() {
+            Ty::Reference { args, .. } => reference_type(
+                &crate::lazy::mark(*index, prefix, Kind::Eager, ""),
+                args,
+                name,
+            ),
+            ty => ty_type(ty, name),
+        },
+        Ty::Seq(ty) | Ty::Set { ty, .. } | Ty::Array { ty, .. } => {
+            format!("Array<{}>", ty_type(ty, name))
+        }
+        Ty::Tuple(items) => {
+            let items = items.iter().map(|ty| ty_type(ty, name)).collect::<Vec<_>>();
+            format!("[{}]", items.join(", "))
+        }
+        // whatever serde_json writes for the key, as a string
+        Ty::Record { key, value } if matches!(key.as_ref(), Ty::Dependency { .. }) => {
+            format!("Partial<Record<string, {}>>", ty_type(value, name))
+        }
+        Ty::Record { key, value } => match ty_type(key, name) {
+            key if key == "string" => format!("Record<string, {}>", ty_type(value, name)),
+            // only some of the keys of an enum need be present
+            key => format!("Partial<Record<{}, {}>>", key, ty_type(value, name)),
+        },
+        Ty::Optional(ty) => format!("{} | undefined", ty_type(ty, name)),
+        Ty::Nullable(ty) => format!("{} | null", ty_type(ty, name)),
+        Ty::WithDefault { ty, .. } | Ty::Lazy { ty, .. } => ty_type(ty, name),
+        Ty::InlineObject(object) => object_type(vec![], &object.fields, object.unknown_keys, name),
+    }
+}
+
+fn reference_type(ident: &str, args: &[Ty], name: &str) -> String {
+    if args.is_empty() {
+        return format!("z.infer<typeof {}>", ident);
+    }
+    // the type parameters of a generic schema are schemas themselves
+    let args = args
+        .iter()
+        .map(|arg| format!("z.ZodType<{}>", ty_type(arg, name)))
+        .collect::<Vec<_>>();
+    format!("z.infer<ReturnType<typeof {}<{}>>>", ident, args.join(", "))
+}
+
+/// `leading` are members written before the fields, like the tag of a tagged union
+fn object_type(
+    leading: Vec<String>,
+    fields: &[Field],
+    unknown_keys: UnknownKeys,
+    name: &str,
+) -> String {
+    let mut members = leading;
+    for field in fields.iter().filter(|field| !field.flatten) {
+        members.push(match &field.ty {
+            Ty::Optional(ty) => format!("{}?: {}", object_key(&field.ident), ty_type(ty, name)),
+            ty => format!("{}: {}", object_key(&field.ident), ty_type(ty, name)),
+        });
+    }
+    let mut parts = vec![match members.is_empty() {
+        true => "{}".to_string(),
+        false => format!("{{ {} }}", members.join("; ")),
+    }];
+    for field in fields.iter().filter(|field| field.flatten) {
+        // a flattened `None` adds no keys at all
+        let (ty, optional) = match &field.ty {
+            Ty::Optional(ty) | Ty::Nullable(ty) => match ty.as_ref() {
+                Ty::Nullable(ty) => (ty.as_ref(), true),
+                ty => (ty, true),
+            },
+            ty => (ty, false),
+        };
+        parts.push(match ty {
+            Ty::Record { value, .. } => format!("{{ [key: string]: {} }}", ty_type(value, name)),
+            ty if optional => format!("Partial<{}>", ty_type(ty, name)),
+            ty => ty_type(ty, name),
+        });
+    }
+    if unknown_keys == UnknownKeys::Passthrough {
+        parts.push("{ [key: string]: unknown }".to_string());
+    }
+    parts.join(" & ")
+}
@@ -1,5 +1,6 @@
 use crate::types::object::InlineObject;
 use crate::Print;
+use proc_macro2::TokenStream;
 use std::fmt::Formatter;
 use std::fmt::Write;
 
@@ -7,19 +8,242 @@ use std::fmt::Write;
 pub enum Ty {
     ZodNumber,
     ZodString,
+    /// a `char`, which serde writes as a string of a single character
+    ZodChar,
+    ZodBoolean,
+    ZodNull,
+    ZodNever,
     InlineObject(InlineObject),
-    Reference(String),
+    /// another exported schema, or a generic one applied to `args`, like `Page(User)`
+    Reference {
+        ident: String,
+        args: Vec<Ty>,
+        /// the Rust type being referred to, which implements `ZodSchema`
+        rust: Option<TokenStream>,
+    },
     Seq(Box<Ty>),
+    /// the key may be missing
     Optional(Box<Ty>),
+    /// `Option<T>`, which serde writes as `null` for `None`
+    Nullable(Box<Ty>),
+    /// a reference to a schema that isn't initialised yet, as it refers back to this one
+    Lazy {
+        ty: Box<Ty>,
+        /// TypeScript infers the schema's type, so the getter is annotated to break the cycle
+        any: bool,
+    },
+    /// a `Ty::Reference` to the type's `index`th dependency, named by that type's `ZodSchema`
+    /// once the source is generated. A bundle routes it through `z.lazy` when it can only
+    /// declare that schema after this one, see [`crate::lazy`]
+    Dependency {
+        index: usize,
+        /// the module path before the name, with `SERDE_ZOD_PATHS=qualified`
+        prefix: String,
+        kind: crate::lazy::Kind,
+        ty: Box<Ty>,
+    },
+    WithDefault {
+        ty: Box<Ty>,
+        value: String,
+    },
+    Set {
+        ty: Box<Ty>,
+        unique: bool,
+    },
+    Array {
+        ty: Box<Ty>,
+        len: usize,
+    },
+    Tuple(Vec<Ty>),
+    Record {
+        key: Box<Ty>,
+        value: Box<Ty>,
+    },
+    /// an integer written as a string of digits, like the keys of a map
+    IntegerString {
+        signed: bool,
+        non_zero: bool,
+    },
+    /// a float written as a string, as serde_json does for map keys
+    FloatString,
+    /// `"true"` or `"false"`, as serde_json writes a `bool` map key
+    BooleanString,
+    /// exactly this string, like the tag of a struct
+    Literal(String),
+    /// zod source given with `#[serde_zod(schema = "...")]`
+    Custom(String),
+    /// an integer within the bounds of its Rust type, which may exclude zero within them, as
+    /// `NonZeroI32` does
+    Int {
+        min: String,
+        max: String,
+        non_zero: bool,
+    },
+    BigInt {
+        min: String,
+        max: String,
+        non_zero: bool,
+    },
 }
 
 impl Ty {
+    pub fn reference(ident: impl Into<String>) -> Self {
+        Self::Reference {
+            ident: ident.into(),
+            args: vec![],
+            rust: None,
+        }
+    }
+    /// Visit every type inside, then this one, so `f` may replace the type it's given
+    pub fn walk_mut(&mut self, f: &mut dyn FnMut(&mut Ty)) {
+        match self {
+            Ty::Reference { args, .. } => args.iter_mut().for_each(|ty| ty.walk_mut(f)),
+            Ty::Seq(ty)
+            | Ty::Optional(ty)
+            | Ty::Nullable(ty)
+            | Ty::Lazy { ty, .. }
+            | Ty::Dependency { ty, .. }
+            | Ty::WithDefault { ty, .. }
+            | Ty::Set { ty, .. }
+            | Ty::Array { ty, .. } => ty.walk_mut(f),
+            Ty::Tuple(items) => items.iter_mut().for_each(|ty| ty.walk_mut(f)),
+            Ty::Record { key, value } => {
+                key.walk_mut(f);
+                value.walk_mut(f);
+            }
+            Ty::InlineObject(object) => object
+                .fields
+                .iter_mut()
+                .for_each(|field| field.ty.walk_mut(f)),
+            Ty::ZodNumber
+            | Ty::ZodString
+            | Ty::ZodChar
+            | Ty::ZodBoolean
+            | Ty::ZodNull
+            | Ty::ZodNever
+            | Ty::IntegerString { .. }
+            | Ty::FloatString
+            | Ty::BooleanString
+            | Ty::Literal(_)
+            | Ty::Custom(_)
+            | Ty::Int { .. }
+            | Ty::BigInt { .. } => {}
+        }
+        f(self)
+    }
+    /// Every `Ty::Reference` inside, including the arguments of generic ones
+    pub fn references<'a>(&'a self, refs: &mut Vec<&'a Ty>) {
+        match self {
+            Ty::Reference { args, .. } => {
+                refs.push(self);
+                args.iter().for_each(|ty| ty.references(refs));
+            }
+            Ty::Seq(ty)
+            | Ty::Optional(ty)
+            | Ty::Nullable(ty)
+            | Ty::Lazy { ty, .. }
+            | Ty::Dependency { ty, .. }
+            | Ty::WithDefault { ty, .. }
+            | Ty::Set { ty, .. }
+            | Ty::Array { ty, .. } => ty.references(refs),
+            Ty::Tuple(items) => items.iter().for_each(|ty| ty.references(refs)),
+            Ty::Record { key, value } => {
+                key.references(refs);
+                value.references(refs);
+            }
+            Ty::InlineObject(object) => object.fields.iter().for_each(|f| f.ty.references(refs)),
+            Ty::ZodNumber
+            | Ty::ZodString
+            | Ty::ZodChar
+            | Ty::ZodBoolean
+            | Ty::ZodNull
+            | Ty::ZodNever
+            | Ty::IntegerString { .. }
+            | Ty::FloatString
+            | Ty::BooleanString
+            | Ty::Literal(_)
+            | Ty::Custom(_)
+            | Ty::Int { .. }
+            | Ty::BigInt { .. } => {}
+        }
+    }
     pub fn seq(ty: Ty) -> Self {
         Self::Seq(Box::new(ty))
     }
     pub fn optional(ty: Ty) -> Self {
         Self::Optional(Box::new(ty))
     }
+    pub fn nullable(ty: Ty) -> Self {
+        Self::Nullable(Box::new(ty))
+    }
+    pub fn with_default(ty: Ty, value: impl Into<String>) -> Self {
+        Self::WithDefault {
+            ty: Box::new(ty),
+            value: value.into(),
+        }
+    }
+    /// `Default::default()` of the Rust type as JSON, when it doesn't depend on user code
+    pub fn default_value(&self) -> Option<String> {
+        match self {
+            Ty::ZodNumber | Ty::Int { .. } => Some("0".into()),
+            Ty::BigInt { .. } => Some("0n".into()),
+            Ty::IntegerString { .. } => Some(crate::quote("0")),
+            Ty::ZodString => Some(crate::quote("")),
+            Ty::ZodBoolean => Some("false".into()),
+            Ty::Seq(_) | Ty::Set { .. } => Some("[]".into()),
+            Ty::Record { .. } => Some("{}".into()),
+            Ty::Nullable(_) => Some("null".into()),
+            _ => None,
+        }
+    }
+    /// Whether a `.default()` somewhere inside makes the input type differ from the output
+    pub fn input_differs(&self) -> bool {
+        match self {
+            Ty::WithDefault { .. } => true,
+            Ty::Seq(ty)
+            | Ty::Optional(ty)
+            | Ty::Nullable(ty)
+            | Ty::Lazy { ty, .. }
+            | Ty::Dependency { ty, .. }
+            | Ty::Set { ty, .. }
+            | Ty::Array { ty, .. } => ty.input_differs(),
+            Ty::Tuple(items) => items.iter().any(Ty::input_differs),
+            Ty::Record { key, value } => key.input_differs() || value.input_differs(),
+            Ty::Reference { args, .. } => args.iter().any(Ty::input_differs),
+            Ty::InlineObject(object) => object.fields.iter().any(crate::Field::input_differs),
+            Ty::ZodNumber
+            | Ty::ZodString
+            | Ty::ZodChar
+            | Ty::ZodBoolean
+            | Ty::ZodNull
+            | Ty::ZodNever
+            | Ty::IntegerString { .. }
+            | Ty::FloatString
+            | Ty::BooleanString
+            | Ty::Literal(_)
+            | Ty::Custom(_)
+            | Ty::Int { .. }
+            | Ty::BigInt { .. } => false,
+        }
+    }
+    pub fn set(ty: Ty, unique: bool) -> Self {
+        Self::Set {
+            ty: Box::new(ty),
+            unique,
+        }
+    }
+    pub fn array(ty: Ty, len: usize) -> Self {
+        Self::Array {
+            ty: Box::new(ty),
+            len,
+        }
+    }
+    pub fn record(key: Ty, value: Ty) -> Self {
+        Self::Record {
+            key: Box::new(key),
+            value: Box::new(value),
+        }
+    }
 }
 
 impl std::fmt::Display for Ty {
@@ -29,13 +253,49 @@ impl std::fmt::Display for Ty {
         let named: String = match self {
             Ty::ZodNumber => "Ty::ZodNumber".to_string(),
             Ty::ZodString => "Ty::ZodString".to_string(),
-            Ty::Reference(_) => "Ty::Reference".to_string(),
+            Ty::ZodChar => "Ty::ZodChar".to_string(),
+            Ty::ZodBoolean => "Ty::ZodBoolean".to_string(),
+            Ty::ZodNull => "Ty::ZodNull".to_string(),
+            Ty::ZodNever => "Ty::ZodNever".to_string(),
+            Ty::Reference { .. } => "Ty::Reference".to_string(),
             Ty::Seq(inner) => {
                 format!("Ty::Seq({})", inner)
             }
             Ty::Optional(inner) => {
                 format!("Ty::Optional({})", inner)
             }
+            Ty::Nullable(inner) => {
+                format!("Ty::Nullable({})", inner)
+            }
+            Ty::Lazy { ty, .. } => {
+                format!("Ty::Lazy({})", ty)
+            }
+            Ty::Dependency { ty, .. } => {
+                format!("Ty::Dependency({})", ty)
+            }
+            Ty::WithDefault { ty, value } => {
+                format!("Ty::WithDefault({}, {})", ty, value)
+            }
+            Ty::Set { ty, .. } => {
+                format!("Ty::Set({})", ty)
+            }
+            Ty::Array { ty, len } => {
+                format!("Ty::Array({}, {})", ty, len)
+            }
+            Ty::Tuple(items) => {
+                let items = items.iter().map(|ty| ty.to_string()).collect::<Vec<_>>();
+                format!("Ty::Tuple({})", items.join(", "))
+            }
+            Ty::Record { key, value } => {
+                format!("Ty::Record({}, {})", key, value)
+            }
+            Ty::IntegerString { .. } => "Ty::IntegerString".to_string(),
+            Ty::FloatString => "Ty::FloatString".to_string(),
+            Ty::BooleanString => "Ty::BooleanString".to_string(),
+            Ty::Literal(_) => "Ty::Literal".to_string(),
+            Ty::Custom(_) => "Ty::Custom".to_string(),
+            Ty::Int { .. } => "Ty::Int".to_string(),
+            Ty::BigInt { .. } => "Ty::BigInt".to_string(),
             Ty::InlineObject(_) => "Ty::InlineObject(..)".to_string(),
         };
         writeln!(f, "{}", named)?;
@@ -48,14 +308,107 @@ impl Print for Ty {
         let res = match self {
             Ty::ZodNumber => "z.number()".to_string(),
             Ty::ZodString => "z.string()".to_string(),
-            Ty::Reference(raw_ref) => raw_ref.to_string(),
-            Ty::Seq(inner) => format!("z.array({})", inner.as_string().expect("local type")),
-            Ty::Optional(inner) => format!(
-                "{}.optional()",
-                inner.as_string().expect("local inner optional type")
+            Ty::ZodChar => "z.string().length(1)".to_string(),
+            Ty::ZodBoolean => "z.boolean()".to_string(),
+            Ty::ZodNull => "z.null()".to_string(),
+            Ty::ZodNever => "z.never()".to_string(),
+            Ty::Reference { ident, args, .. } if args.is_empty() => ident.to_string(),
+            Ty::Reference { ident, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|ty| ty.as_string())
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("{}({})", ident, args.join(", "))
+            }
+            Ty::Seq(inner) => format!("z.array({})", inner.as_string()?),
+            Ty::Optional(inner) => match inner.as_ref() {
+                Ty::Nullable(inner) => format!("{}.nullish()", inner.as_string()?),
+                inner => format!("{}.optional()", inner.as_string()?),
+            },
+            Ty::Nullable(inner) => format!("{}.nullable()", inner.as_string()?),
+            Ty::Lazy { ty, any: false } => format!("z.lazy(() => {})", ty.as_string()?),
+            Ty::Lazy { ty, any: true } => {
+                format!("z.lazy((): z.ZodTypeAny => {})", ty.as_string()?)
+            }
+            Ty::Dependency {
+                index,
+                prefix,
+                kind,
+                ty,
+            } => {
+                // the arguments of a generic schema follow its name
+                let printed = ty.as_string()?;
+                let rest = match ty.as_ref() {
+                    Ty::Reference { ident, .. } => printed.strip_prefix(ident.as_str()),
+                    _ => None,
+                };
+                crate::lazy::mark(*index, prefix, *kind, rest.unwrap_or_default())
+            }
+            Ty::Set { ty, unique: false } => format!("z.array({})", ty.as_string()?),
+            Ty::Set { ty, unique: true } => format!(
+                "z.array({}).refine((items) => new Set(items).size === items.length, {})",
+                ty.as_string()?,
+                crate::quote("Expected unique items")
+            ),
+            Ty::Array { ty, len } => format!("z.array({}).length({})", ty.as_string()?, len),
+            Ty::Tuple(items) => {
+                let items = items
+                    .iter()
+                    .map(|ty| ty.as_string())
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("z.tuple([{}])", items.join(", "))
+            }
+            Ty::WithDefault { ty, value } => format!("{}.default({})", ty.as_string()?, value),
+            Ty::Record { key, value } => {
+                format!("z.record({}, {})", key.as_string()?, value.as_string()?)
+            }
+            Ty::IntegerString { signed, non_zero } => format!(
+                r"z.string().regex(/^{}{}$/)",
+                if *signed { "-?" } else { "" },
+                if *non_zero { r"[1-9]\d*" } else { r"\d+" }
+            ),
+            Ty::Literal(lit) => format!("z.literal({})", crate::quote(lit)),
+            Ty::Custom(source) => source.clone(),
+            Ty::FloatString => r"z.string().regex(/^-?\d+(\.\d+)?(e-?\d+)?$/)".to_string(),
+            Ty::BooleanString => format!(
+                "z.enum([{}, {}])",
+                crate::quote("true"),
+                crate::quote("false")
+            ),
+            Ty::Int { min, max, non_zero } => format!(
+                "z.number().int().min({}).max({}){}",
+                min,
+                max,
+                non_zero_refinement(*non_zero, "0")
+            ),
+            Ty::BigInt { min, max, non_zero } => format!(
+                "z.bigint().min({}).max({}){}",
+                min,
+                max,
+                non_zero_refinement(*non_zero, "0n")
             ),
             Ty::InlineObject(fields) => fields.as_string()?,
         };
         write!(x, "{}", res)
     }
 }
+
+/// Signed `NonZero` integers have zero within their bounds, so it's rejected separately
+fn non_zero_refinement(non_zero: bool, zero: &str) -> String {
+    match non_zero {
+        true => format!(
+            ".refine((n) => n !== {}, {})",
+            zero,
+            crate::quote("Expected a non-zero integer")
+        ),
+        false => String::new(),
+    }
+}
+
+#[test]
+fn test_print_unique_set() -> Result<(), std::fmt::Error> {
+    let printed = Ty::set(Ty::ZodString, true).as_string()?;
+    let expected = r#"z.array(z.string()).refine((items) => new Set(items).size === items.length, "Expected unique items")"#;
+    assert_eq!(expected, printed);
+    Ok(())
+}
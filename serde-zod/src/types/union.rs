@@ -1,18 +1,24 @@
+use crate::attrs::Mode;
+use crate::case::RenameRule;
 use crate::printer::{Print, Printer};
-use crate::types::object::InlineObject;
+use crate::types::object::{InlineObject, UnknownKeys};
 use crate::types::ty::Ty;
-use crate::{as_ty, Field};
+use crate::{unnamed_fields_ty, Field};
 use std::fmt::Write;
 
 #[derive(Debug)]
 pub struct Union {
     pub ident: String,
     pub variants: Vec<UnionVariant>,
+    /// applied to the fields of struct variants
+    pub unknown_keys: UnknownKeys,
 }
 
 #[derive(Debug)]
 pub struct UnionVariant {
     pub ident: String,
+    /// other names accepted in place of `ident`, via `#[serde(alias = "..")]`
+    pub aliases: Vec<String>,
     pub fields: UnionVariantFields,
 }
 
@@ -20,37 +26,48 @@ impl UnionVariant {
     pub fn from_syn_fields_named(
         ident: impl Into<String>,
         fields_named: &syn::FieldsNamed,
-    ) -> Option<Self> {
-        let fields = fields_named
-            .named
-            .iter()
-            .filter_map(Field::from_syn_field)
-            .collect();
+        rename_all: RenameRule,
+        mode: Mode,
+    ) -> syn::Result<Self> {
+        let fields = crate::error::collect(
+            fields_named
+                .named
+                .iter()
+                .map(|field| Field::from_syn_field(field, rename_all, mode)),
+        )?;
         let tuv = Self {
             ident: ident.into(),
-            fields: UnionVariantFields::Named(fields),
+            aliases: vec![],
+            fields: UnionVariantFields::Named(fields.into_iter().flatten().collect()),
         };
-        Some(tuv)
+        Ok(tuv)
     }
     pub fn from_syn_fields_unnamed(
         ident: impl Into<String>,
         unnamed: &syn::FieldsUnnamed,
-    ) -> Option<Self> {
-        unnamed
-            .unnamed
-            .first()
-            .and_then(|first| as_ty(&first.ty).ok())
-            .map(|ty| Self {
-                ident: ident.into(),
-                fields: UnionVariantFields::Unnamed(ty),
-            })
+        mode: Mode,
+    ) -> syn::Result<Self> {
+        unnamed_fields_ty(unnamed, mode).map(|ty| Self {
+            ident: ident.into(),
+            aliases: vec![],
+            fields: UnionVariantFields::Unnamed(ty),
+        })
     }
     pub fn from_unit(ident: impl Into<String>) -> Self {
         Self {
             ident: ident.into(),
+            aliases: vec![],
             fields: UnionVariantFields::Unit,
         }
     }
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+    /// The variant name followed by its aliases, each of which is a valid representation
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.ident).chain(self.aliases.iter())
+    }
 }
 
 #[derive(Debug)]
@@ -62,43 +79,51 @@ pub enum UnionVariantFields {
 
 impl Print for Union {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
-        let mut printer = Printer::new();
-        printer.writeln("z.union([")?;
-        printer.indent();
+        let mut options = vec![];
         for x in &self.variants {
-            match &x.fields {
-                UnionVariantFields::Unit => {
-                    let as_lit = crate::Literal {
-                        lit: x.ident.clone(),
-                    };
-                    printer.line(as_lit.as_string()?);
-                }
-                UnionVariantFields::Named(fields) => {
-                    let object_key = x.ident.clone();
-                    let ident_obj = crate::types::object::Object {
-                        ident: object_key,
-                        fields: vec![Field {
-                            ident: x.ident.clone(),
-                            ty: Ty::InlineObject(InlineObject {
-                                fields: fields.clone(),
-                            }),
-                        }],
-                    };
-                    printer.line(&ident_obj.as_string()?);
-                }
-                UnionVariantFields::Unnamed(ty) => {
-                    let object_key = x.ident.clone();
-                    let as_obj = crate::types::object::Object {
-                        ident: object_key,
-                        fields: vec![Field {
-                            ident: x.ident.clone(),
-                            ty: ty.clone(),
-                        }],
-                    };
-                    printer.line(&as_obj.as_string()?);
+            for name in x.names() {
+                match &x.fields {
+                    UnionVariantFields::Unit => {
+                        let as_lit = crate::Literal { lit: name.clone() };
+                        options.push(as_lit.as_string()?);
+                    }
+                    UnionVariantFields::Named(fields) => {
+                        let ident_obj = crate::types::object::Object {
+                            ident: name.clone(),
+                            fields: vec![Field::new(
+                                name.clone(),
+                                Ty::InlineObject(InlineObject {
+                                    fields: fields.clone(),
+                                    unknown_keys: self.unknown_keys,
+                                }),
+                            )],
+                            unknown_keys: UnknownKeys::Strip,
+                        };
+                        options.push(ident_obj.as_string()?);
+                    }
+                    UnionVariantFields::Unnamed(ty) => {
+                        let as_obj = crate::types::object::Object {
+                            ident: name.clone(),
+                            fields: vec![Field::new(name.clone(), ty.clone())],
+                            unknown_keys: UnknownKeys::Strip,
+                        };
+                        options.push(as_obj.as_string()?);
+                    }
                 }
             }
         }
+
+        // `z.union` needs at least two options
+        if let [option] = options.as_slice() {
+            return write!(x, "{}", option.trim_end());
+        }
+
+        let mut printer = Printer::new();
+        printer.writeln("z.union([")?;
+        printer.indent();
+        for option in options {
+            printer.line(option);
+        }
         printer.join_lines(',')?;
         printer.dedent();
         printer.write("])")?;
@@ -110,23 +135,25 @@ impl Print for Union {
 fn test_print_union() -> Result<(), std::fmt::Error> {
     let t = Union {
         ident: String::from("Count"),
+        unknown_keys: UnknownKeys::Strip,
         variants: vec![
             UnionVariant {
+                aliases: vec![],
                 ident: "Two".into(),
                 fields: UnionVariantFields::Unnamed(Ty::ZodString),
             },
             UnionVariant {
+                aliases: vec![],
                 ident: "TwoOther".into(),
                 fields: UnionVariantFields::Unit,
             },
             UnionVariant {
+                aliases: vec![],
                 ident: "TwoOtherReally".into(),
-                fields: UnionVariantFields::Named(vec![Field {
-                    ident: "named_1".into(),
-                    ty: Ty::ZodNumber,
-                }]),
+                fields: UnionVariantFields::Named(vec![Field::new("named_1", Ty::ZodNumber)]),
             },
             UnionVariant {
+                aliases: vec![],
                 ident: "Three".into(),
                 fields: UnionVariantFields::Unnamed(Ty::Optional(Box::new(Ty::ZodString))),
             },
@@ -146,8 +173,25 @@ fn test_print_union() -> Result<(), std::fmt::Error> {
     Three: z.string().optional(),
   }),
 ])"#;
-    // let litt = Item::Lit(Literal { lit: "Two".into() }).as_string()?;
     let printed = t.as_string()?;
     assert_eq!(expected, printed);
     Ok(())
 }
+
+#[test]
+fn test_print_single_variant() -> Result<(), std::fmt::Error> {
+    let t = Union {
+        ident: String::from("Only"),
+        unknown_keys: UnknownKeys::Strip,
+        variants: vec![UnionVariant {
+            aliases: vec![],
+            ident: "Pair".into(),
+            fields: UnionVariantFields::Unnamed(Ty::ZodString),
+        }],
+    };
+    let expected = r#"z.object({
+  Pair: z.string(),
+})"#;
+    assert_eq!(expected, t.as_string()?);
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::printer::{Print, Printer};
+use crate::types::object::{InlineObject, UnknownKeys};
+use crate::types::ty::Ty;
+use crate::union::{UnionVariant, UnionVariantFields};
+use std::fmt::Write;
+
+/// `#[serde(untagged)]`: each variant is written as its bare payload, and serde
+/// picks the first variant that matches when reading
+#[derive(Debug)]
+pub struct UntaggedUnion {
+    pub ident: String,
+    pub variants: Vec<UnionVariant>,
+    /// applied to the fields of struct variants
+    pub unknown_keys: UnknownKeys,
+}
+
+impl UntaggedUnion {
+    pub fn new(ident: impl Into<String>) -> Self {
+        Self {
+            ident: ident.into(),
+            variants: vec![],
+            unknown_keys: UnknownKeys::Strip,
+        }
+    }
+    pub fn add_variants(&mut self, variants: Vec<UnionVariant>) {
+        self.variants.extend(variants);
+    }
+}
+
+impl Print for UntaggedUnion {
+    fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
+        let mut payloads = vec![];
+        for variant in &self.variants {
+            payloads.push(match &variant.fields {
+                UnionVariantFields::Unit => Ty::ZodNull.as_string()?,
+                UnionVariantFields::Named(fields) => InlineObject {
+                    fields: fields.clone(),
+                    unknown_keys: self.unknown_keys,
+                }
+                .as_string()?,
+                UnionVariantFields::Unnamed(ty) => ty.as_string()?,
+            });
+        }
+
+        // `z.union` needs at least two options
+        if let [payload] = payloads.as_slice() {
+            return writeln!(x, "{}", payload.trim_end());
+        }
+
+        let mut printer = Printer::new();
+        printer.writeln("z.union([")?;
+        printer.indent();
+        for payload in payloads {
+            printer.line(payload);
+        }
+        printer.join_lines(',')?;
+        printer.dedent();
+        printer.writeln("])")?;
+        write!(x, "{}", printer.dump())
+    }
+}
+
+#[test]
+fn test_print_single_variant() -> Result<(), std::fmt::Error> {
+    let mut t = UntaggedUnion::new("Id");
+    t.add_variants(vec![UnionVariant {
+        ident: "Id".into(),
+        aliases: vec![],
+        fields: UnionVariantFields::Unnamed(Ty::ZodString),
+    }]);
+    assert_eq!("z.string()\n", t.as_string()?);
+    Ok(())
+}
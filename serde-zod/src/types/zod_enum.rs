@@ -22,6 +22,8 @@ impl Enum {
 #[derive(Debug)]
 pub struct EnumUnitVariant {
     pub ident: String,
+    /// other names accepted in place of `ident`, via `#[serde(alias = "..")]`
+    pub aliases: Vec<String>,
 }
 
 impl Print for Enum {
@@ -30,7 +32,10 @@ impl Print for Enum {
         printer.writeln("z.enum([")?;
         printer.indent();
         for x in &self.variants {
-            printer.line(&crate::quote(&x.ident));
+            printer.line(crate::quote(&x.ident));
+            for alias in &x.aliases {
+                printer.line(crate::quote(alias));
+            }
         }
         printer.join_lines(',')?;
         printer.dedent();
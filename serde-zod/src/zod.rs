@@ -2,15 +2,31 @@ use super::*;
 
 use crate::printer::{Print, Printer};
 use crate::types::import::Import;
+use crate::types::named_ty::NamedTy;
 use crate::types::object::Object;
 use crate::types::tagged_union::TaggedUnion;
-use crate::types::union::Union;
+use crate::types::union::{Union, UnionVariant, UnionVariantFields};
+use crate::types::untagged_union::UntaggedUnion;
 use crate::types::zod_enum::Enum;
 use std::fmt::Write;
 
 #[derive(Debug)]
 pub enum Statement {
     Export(Item),
+    /// a generic type, exported as a function from the schemas of its type parameters
+    ExportGeneric {
+        params: Vec<String>,
+        item: Item,
+    },
+    /// a schema that refers to itself, which needs its TS type written out
+    ExportRecursive(Item),
+    /// the TS type of an exported schema, via `z.infer`
+    TypeAlias {
+        ident: String,
+        params: Vec<String>,
+        /// also export `z.input` and `z.output` types, as defaults or transforms make them differ
+        input_differs: bool,
+    },
 }
 
 #[derive(Debug)]
@@ -19,37 +35,203 @@ pub struct StatementList(pub Vec<Statement>);
 impl Print for Statement {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
         let mut printer = Printer::new();
-        let (ident, inner) = match self {
-            Statement::Export(Item::TaggedUnion(tu)) => (&tu.ident, tu.as_string()?),
-            Statement::Export(Item::Object(ob)) => (&ob.ident, ob.as_string()?),
-            Statement::Export(Item::Enum(en)) => (&en.ident, en.as_string()?),
-            Statement::Export(Item::Lit(lit)) => (&lit.lit, lit.as_string()?),
-            Statement::Export(Item::Union(union)) => (&union.ident, union.as_string()?),
+        let (item, params) = match self {
+            Statement::Export(item) => (item, None),
+            Statement::ExportRecursive(item) => return print_recursive(item, x),
+            Statement::ExportGeneric { params, item } => (item, Some(params)),
+            Statement::TypeAlias {
+                ident,
+                params,
+                input_differs,
+            } => return print_type_aliases(ident, params, *input_differs, x),
         };
-        printer.writeln(format!("export const {} =", ident))?;
+        let (ident, inner) = (item.ident(), item.as_string()?);
+        match params {
+            None => printer.writeln(format!("export const {} =", ident))?,
+            Some(params) => {
+                let bounds = params
+                    .iter()
+                    .map(|param| format!("{} extends z.ZodTypeAny", param))
+                    .collect::<Vec<_>>();
+                let args = params
+                    .iter()
+                    .map(|param| format!("{}: {}", param, param))
+                    .collect::<Vec<_>>();
+                printer.writeln(format!(
+                    "export const {} = <{}>({}) =>",
+                    ident,
+                    bounds.join(", "),
+                    args.join(", ")
+                ))?
+            }
+        }
         printer.indent();
         printer.write(inner)?;
         write!(x, "{}", printer.dump())
     }
 }
 
+/// `export type Node = ...` followed by the schema annotated with it, since TypeScript can't
+/// infer the type of a value that refers to itself
+fn print_recursive(item: &Item, x: &mut String) -> Result<(), std::fmt::Error> {
+    let ident = item.ident();
+    let ts_type = crate::types::ts::item_type(item, ident);
+    writeln!(x, "export type {} = {};", ident, ts_type)?;
+    // the input type isn't written out, so it's left to the schema to check
+    let annotation = match item.input_differs() {
+        true => format!("z.ZodType<{}, z.ZodTypeDef, unknown>", ident),
+        false => format!("z.ZodType<{}>", ident),
+    };
+    let mut printer = Printer::new();
+    printer.writeln(format!("export const {}: {} =", ident, annotation))?;
+    printer.indent();
+    printer.write(item.as_string()?)?;
+    write!(x, "{}", printer.dump())
+}
+
+fn print_type_aliases(
+    ident: &str,
+    params: &[String],
+    input_differs: bool,
+    x: &mut String,
+) -> Result<(), std::fmt::Error> {
+    let mut aliases = vec![("", "infer")];
+    if input_differs {
+        aliases.extend([("Input", "input"), ("Output", "output")]);
+    }
+    for (suffix, helper) in aliases {
+        if params.is_empty() {
+            writeln!(
+                x,
+                "export type {}{} = z.{}<typeof {}>;",
+                ident, suffix, helper, ident
+            )?;
+        } else {
+            // instantiation expressions (TS 4.7) give the schema for the given parameters
+            let bounds = params
+                .iter()
+                .map(|param| format!("{} extends z.ZodTypeAny", param))
+                .collect::<Vec<_>>();
+            writeln!(
+                x,
+                "export type {}{}<{}> = z.{}<ReturnType<typeof {}<{}>>>;",
+                ident,
+                suffix,
+                bounds.join(", "),
+                helper,
+                ident,
+                params.join(", ")
+            )?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum Item {
-    Lit(Literal),
     Enum(Enum),
     Union(Union),
     TaggedUnion(TaggedUnion),
+    UntaggedUnion(UntaggedUnion),
     Object(Object),
+    Ty(NamedTy),
+}
+
+impl Item {
+    pub fn ident(&self) -> &str {
+        match self {
+            Item::TaggedUnion(tu) => &tu.ident,
+            Item::Object(ob) => &ob.ident,
+            Item::Enum(en) => &en.ident,
+            Item::Union(union) => &union.ident,
+            Item::Ty(named) => &named.ident,
+            Item::UntaggedUnion(union) => &union.ident,
+        }
+    }
+    /// Whether the type accepted by the schema differs from the type it produces, through a
+    /// `.default()` or the `z.preprocess` that renames aliases. Other schemas referenced by
+    /// this one aren't known here, so their defaults are missed
+    pub fn input_differs(&self) -> bool {
+        let variants = |variants: &[UnionVariant]| {
+            variants.iter().any(|variant| match &variant.fields {
+                UnionVariantFields::Unit => false,
+                UnionVariantFields::Named(fields) => fields.iter().any(Field::input_differs),
+                UnionVariantFields::Unnamed(ty) => ty.input_differs(),
+            })
+        };
+        match self {
+            Item::Enum(_) => false,
+            Item::Union(union) => variants(&union.variants),
+            Item::TaggedUnion(union) => variants(&union.variants),
+            Item::UntaggedUnion(union) => variants(&union.variants),
+            Item::Object(object) => object.fields.iter().any(Field::input_differs),
+            Item::Ty(named) => named.ty.input_differs(),
+        }
+    }
+    /// See [`Ty::walk_mut`]
+    pub fn walk_mut(&mut self, f: &mut dyn FnMut(&mut Ty)) {
+        self.walk_fields_mut(&mut |ty, _| ty.walk_mut(f))
+    }
+    /// Each type directly inside, along with whether it's a `#[serde(flatten)]` field
+    pub fn walk_fields_mut(&mut self, f: &mut dyn FnMut(&mut Ty, bool)) {
+        let mut variants = |variants: &mut [UnionVariant]| {
+            for variant in variants {
+                match &mut variant.fields {
+                    UnionVariantFields::Unit => {}
+                    UnionVariantFields::Named(fields) => fields
+                        .iter_mut()
+                        .for_each(|field| f(&mut field.ty, field.flatten)),
+                    UnionVariantFields::Unnamed(ty) => f(ty, false),
+                }
+            }
+        };
+        match self {
+            Item::Enum(_) => {}
+            Item::Union(union) => variants(&mut union.variants),
+            Item::TaggedUnion(union) => variants(&mut union.variants),
+            Item::UntaggedUnion(union) => variants(&mut union.variants),
+            Item::Object(object) => object
+                .fields
+                .iter_mut()
+                .for_each(|field| f(&mut field.ty, field.flatten)),
+            Item::Ty(named) => f(&mut named.ty, false),
+        }
+    }
+    /// Every `Ty::Reference` inside, so the schemas it refers to by name
+    pub fn references<'a>(&'a self) -> Vec<&'a Ty> {
+        let mut refs = vec![];
+        let variants = |variants: &'a [UnionVariant]| {
+            variants
+                .iter()
+                .flat_map(|variant| match &variant.fields {
+                    UnionVariantFields::Unit => vec![],
+                    UnionVariantFields::Named(fields) => fields.iter().map(|f| &f.ty).collect(),
+                    UnionVariantFields::Unnamed(ty) => vec![ty],
+                })
+                .collect::<Vec<_>>()
+        };
+        let tys = match self {
+            Item::Enum(_) => vec![],
+            Item::Union(union) => variants(&union.variants),
+            Item::TaggedUnion(union) => variants(&union.variants),
+            Item::UntaggedUnion(union) => variants(&union.variants),
+            Item::Object(object) => object.fields.iter().map(|f| &f.ty).collect(),
+            Item::Ty(named) => vec![&named.ty],
+        };
+        tys.into_iter().for_each(|ty| ty.references(&mut refs));
+        refs
+    }
 }
 
 impl Print for Item {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
         match self {
-            Item::Lit(lit) => lit.print(x),
             Item::Enum(eenum) => eenum.print(x),
             Item::TaggedUnion(tu) => tu.print(x),
             Item::Object(obj) => obj.print(x),
             Item::Union(uni) => uni.print(x),
+            Item::Ty(named) => named.print(x),
+            Item::UntaggedUnion(uni) => uni.print(x),
         }
     }
 }
@@ -63,6 +245,10 @@ pub struct Program {
 impl Print for Vec<Statement> {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
         for statement in self {
+            // not every item ends its last line
+            if !x.is_empty() && !x.ends_with('\n') {
+                x.push('\n');
+            }
             statement.print(x)?;
         }
         Ok(())
@@ -81,6 +267,10 @@ impl Print for Program {
 pub struct Field {
     pub ident: String,
     pub ty: Ty,
+    /// other keys accepted in place of `ident`, via `#[serde(alias = "..")]`
+    pub aliases: Vec<String>,
+    /// `#[serde(flatten)]`, the contents sit directly in the parent object
+    pub flatten: bool,
 }
 
 impl Field {
@@ -88,20 +278,72 @@ impl Field {
         Self {
             ident: ident.into(),
             ty,
+            aliases: vec![],
+            flatten: false,
         }
     }
-    pub fn from_syn_field(field: &syn::Field) -> Option<Self> {
-        match (&field.ident, as_ty(&field.ty).ok()) {
-            (Some(ident), Some(ty)) => Some(Self::new(ident.to_string(), ty)),
-            _ => None,
+    /// The field as it appears in the schema, or `None` when serde skips it
+    pub fn from_syn_field(
+        field: &syn::Field,
+        rename_all: RenameRule,
+        mode: Mode,
+    ) -> syn::Result<Option<Self>> {
+        let field_attrs = FieldAttrs::from_attrs(&field.attrs, mode)?;
+        if field_attrs.skipped(mode) {
+            return Ok(None);
         }
+        let ident = field
+            .ident
+            .as_ref()
+            .ok_or_else(|| syn::Error::new_spanned(field, "expected a named field"))?;
+        let ty = field_ty(field, &field_attrs)?;
+        Ok(Some(Self::from_attrs(
+            ident,
+            ty,
+            &field_attrs,
+            rename_all,
+            mode,
+        )))
+    }
+    /// Apply the field's `#[serde(...)]` attributes to its name and type
+    pub fn from_attrs(
+        ident: &syn::Ident,
+        ty: Ty,
+        field_attrs: &FieldAttrs,
+        rename_all: RenameRule,
+        mode: Mode,
+    ) -> Self {
+        let ty = match ty {
+            Ty::Optional(_) => ty,
+            // serde reads a missing `Option` field as `None`
+            Ty::Nullable(_) if mode == Mode::Input => Ty::optional(ty),
+            // `None` is never written, so the key is missing rather than `null`
+            Ty::Nullable(inner) if field_attrs.skips_none(mode) => Ty::Optional(inner),
+            ty if field_attrs.may_be_missing(mode) => match field_attrs.default_value(&ty, mode) {
+                Some(value) => Ty::with_default(ty, value),
+                None => Ty::optional(ty),
+            },
+            ty => ty,
+        };
+        Self {
+            ident: field_attrs.name(ident, rename_all, mode),
+            ty,
+            aliases: field_attrs.aliases(mode),
+            flatten: field_attrs.flatten,
+        }
+    }
+}
+
+impl Field {
+    pub fn input_differs(&self) -> bool {
+        !self.aliases.is_empty() || self.ty.input_differs()
     }
 }
 
 impl Print for Field {
     fn print(&self, x: &mut String) -> Result<(), std::fmt::Error> {
         let ty_string = self.ty.as_string()?;
-        write!(x, "{}: {}", self.ident, ty_string)
+        write!(x, "{}: {}", object_key(&self.ident), ty_string)
     }
 }
 